@@ -33,6 +33,14 @@ pub fn sound_effect(handle: Handle<AudioSource>) -> impl Bundle {
     (AudioPlayer(handle), PlaybackSettings::DESPAWN, SoundEffect)
 }
 
+/// A sound effect audio instance with its own speed, volume... e.g. to vary repeated sounds.
+pub fn sound_effect_with_settings(
+    handle: Handle<AudioSource>,
+    settings: PlaybackSettings,
+) -> impl Bundle {
+    (AudioPlayer(handle), settings, SoundEffect)
+}
+
 /// [`GlobalVolume`] doesn't apply to already-running audio entities, so this system will update them.
 fn apply_global_volume(
     global_volume: Res<GlobalVolume>,
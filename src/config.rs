@@ -0,0 +1,105 @@
+//! Files the player can edit by hand, like the input bindings or the settings. They are stored as
+//! RON, relative to the working directory, and only on native builds.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, de::DeserializeOwned};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Could not access {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Could not read {}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        source: ron::error::SpannedError,
+    },
+    #[error("Could not write {}: {source}", path.display())]
+    Serialize { path: PathBuf, source: ron::Error },
+}
+
+/// The value saved at `path`, or `None` if there is no file there.
+pub fn read<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<Option<T>, ConfigError> {
+    let path = path.as_ref();
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(source) => {
+            return Err(ConfigError::Io {
+                path: path.to_owned(),
+                source,
+            });
+        }
+    };
+    ron::from_str(&text)
+        .map(Some)
+        .map_err(|source| ConfigError::Parse {
+            path: path.to_owned(),
+            source,
+        })
+}
+
+/// Saves `value` at `path`, creating its directory if needed.
+pub fn write<T: Serialize>(path: impl AsRef<Path>, value: &T) -> Result<(), ConfigError> {
+    let path = path.as_ref();
+    let text =
+        ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()).map_err(|source| {
+            ConfigError::Serialize {
+                path: path.to_owned(),
+                source,
+            }
+        })?;
+    let io_error = |source| ConfigError::Io {
+        path: path.to_owned(),
+        source,
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    std::fs::write(path, text).map_err(io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    /// A directory of its own for each test, as they run in parallel.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("config-test-{}", std::process::id()))
+            .join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn written_values_read_back() {
+        let path = test_dir("round_trip").join("nested/values.ron");
+        let value = BTreeMap::from([("shake".to_string(), true), ("zoom".to_string(), false)]);
+
+        write(&path, &value).unwrap();
+        assert_eq!(read::<BTreeMap<String, bool>>(&path).unwrap(), Some(value));
+    }
+
+    #[test]
+    fn missing_files_are_not_errors() {
+        let path = test_dir("missing").join("values.ron");
+        assert!(matches!(read::<bool>(&path), Ok(None)));
+    }
+
+    #[test]
+    fn invalid_files_are_parse_errors() {
+        let path = test_dir("invalid").join("values.ron");
+        write(&path, &"not a number").unwrap();
+        assert!(matches!(
+            read::<u32>(&path),
+            Err(ConfigError::Parse { path: error_path, .. }) if error_path == path
+        ));
+    }
+}
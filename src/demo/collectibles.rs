@@ -0,0 +1,204 @@
+//! Pickups: coins and potions the player collects by walking over them.
+//!
+//! Props whose class names an [`Item`] become collectible. A Tiled object can give more than one
+//! with an `amount` property.
+
+use std::{collections::BTreeMap, fmt};
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use tiled::PropertyValue;
+
+use crate::{
+    asset_tracking::LoadResource,
+    audio::sound_effect,
+    demo::{player::Player, props::Prop},
+    screens::Screen,
+    theme::widget,
+    utils::tiled::TiledObject,
+};
+
+/// Radius of the sensor of a pickup, smaller than its sprite so that it has to be touched.
+pub const COLLECTIBLE_RADIUS: f32 = 5.0;
+/// The custom property of Tiled objects giving how many items a pickup holds.
+pub const AMOUNT_PROPERTY: &str = "amount";
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Inventory>();
+    app.load_resource::<CollectibleAssets>();
+    app.add_observer(make_prop_collectible);
+    app.add_observer(pick_up_collectible);
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (reset_inventory, spawn_inventory_hud),
+    );
+    app.add_systems(
+        Update,
+        update_inventory_hud.run_if(in_state(Screen::Gameplay).and(resource_changed::<Inventory>)),
+    );
+}
+
+/// Something that can be picked up and counted in the [`Inventory`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect)]
+pub enum Item {
+    Coin,
+    BlueCoin,
+    RedPotion,
+    BluePotion,
+    WhitePotion,
+}
+
+impl Item {
+    /// The item a prop of this class is.
+    pub fn from_class(class: &str) -> Option<Self> {
+        match class {
+            "Coin" => Some(Item::Coin),
+            "BlueCoin" => Some(Item::BlueCoin),
+            "PotionRed" => Some(Item::RedPotion),
+            "PotionBlue" => Some(Item::BluePotion),
+            "PotionWhite" => Some(Item::WhitePotion),
+            _ => None,
+        }
+    }
+
+    /// How much one of this item adds to the score.
+    pub fn score(self) -> u32 {
+        match self {
+            Item::Coin => 1,
+            Item::BlueCoin => 5,
+            Item::RedPotion | Item::BluePotion | Item::WhitePotion => 0,
+        }
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Item::Coin => "Coins",
+            Item::BlueCoin => "Blue Coins",
+            Item::RedPotion => "Red Potions",
+            Item::BluePotion => "Blue Potions",
+            Item::WhitePotion => "White Potions",
+        })
+    }
+}
+
+/// A pickup, which goes to the [`Inventory`] when the [`Player`] touches it.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+#[require(Sensor, CollisionEventsEnabled)]
+pub struct Collectible {
+    pub item: Item,
+    pub amount: u32,
+}
+
+/// What the player picked up since the game started.
+#[derive(Resource, Debug, Default, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct Inventory {
+    items: BTreeMap<Item, u32>,
+}
+
+impl Inventory {
+    pub fn add(&mut self, item: Item, amount: u32) {
+        *self.items.entry(item).or_default() += amount;
+    }
+
+    pub fn score(&self) -> u32 {
+        self.items
+            .iter()
+            .map(|(item, count)| item.score() * count)
+            .sum()
+    }
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct CollectibleAssets {
+    /// There is no dedicated pickup sound yet.
+    #[dependency]
+    pickup: Handle<AudioSource>,
+}
+
+impl FromWorld for CollectibleAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            pickup: assets.load("audio/sound_effects/button_click.ogg"),
+        }
+    }
+}
+
+fn make_prop_collectible(
+    add: On<Add, Prop>,
+    mut commands: Commands,
+    prop_query: Query<(&Prop, Option<&TiledObject>)>,
+) {
+    let Ok((prop, object)) = prop_query.get(add.entity) else {
+        return;
+    };
+    let Some(item) = Item::from_class(&prop.class) else {
+        return;
+    };
+    let amount = match object.and_then(|object| object.properties.get(AMOUNT_PROPERTY)) {
+        Some(&PropertyValue::IntValue(amount)) => amount.max(0) as u32,
+        _ => 1,
+    };
+    commands.entity(add.entity).insert((
+        Collectible { item, amount },
+        Collider::circle(COLLECTIBLE_RADIUS),
+    ));
+}
+
+fn pick_up_collectible(
+    collision: On<CollisionStart>,
+    mut commands: Commands,
+    mut inventory: ResMut<Inventory>,
+    collectible_assets: If<Res<CollectibleAssets>>,
+    collectible_query: Query<&Collectible>,
+    player_query: Query<(), With<Player>>,
+) {
+    let Ok(collectible) = collectible_query.get(collision.collider1) else {
+        return;
+    };
+    if !player_query.contains(collision.body2.unwrap_or(collision.collider2)) {
+        return;
+    }
+    inventory.add(collectible.item, collectible.amount);
+    commands.entity(collision.collider1).despawn();
+    commands.spawn(sound_effect(collectible_assets.pickup.clone()));
+}
+
+fn reset_inventory(mut inventory: ResMut<Inventory>) {
+    *inventory = Inventory::default();
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct InventoryLabel;
+
+fn spawn_inventory_hud(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Inventory HUD"),
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(12),
+            left: px(12),
+            ..default()
+        },
+        Pickable::IGNORE,
+        DespawnOnExit(Screen::Gameplay),
+        children![(widget::label(""), InventoryLabel)],
+    ));
+}
+
+fn update_inventory_hud(
+    inventory: Res<Inventory>,
+    mut label: Single<&mut Text, With<InventoryLabel>>,
+) {
+    let mut text = format!("Score {}", inventory.score());
+    for (item, count) in &inventory.items {
+        text.push_str(&format!("   {item} x{count}"));
+    }
+    label.0 = text;
+}
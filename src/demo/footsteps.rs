@@ -0,0 +1,243 @@
+//! Footstep sounds that depend on what is walked on.
+//!
+//! Walk clips send a [`STEP_EVENT`] when a foot touches the ground. The sound then comes from the
+//! bank of the [`Surface`] under the character, named by the [`SURFACE_PROPERTY`] of the tile or
+//! of its layer in Tiled. Characters other than the player sound softer the further away they are.
+
+use bevy::{audio::Volume, prelude::*};
+use rand::prelude::*;
+use tiled::PropertyValue;
+
+use crate::{
+    asset_tracking::LoadResource,
+    audio::sound_effect_with_settings,
+    demo::player::Player,
+    utils::{
+        animator::AnimationEvent,
+        tiled::{TiledMapHandle, query::TiledMapQuery},
+    },
+};
+
+/// The animation event of a foot touching the ground.
+pub const STEP_EVENT: &str = "step";
+/// The custom property of tiles and layers naming their [`Surface`].
+pub const SURFACE_PROPERTY: &str = "surface";
+/// How far from the player the footsteps of other characters can be heard, in world units.
+pub const FOOTSTEP_HEARING_RANGE: f32 = 160.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.load_resource::<FootstepAssets>();
+    app.add_observer(play_footstep);
+}
+
+/// What a tile is made of, as far as footsteps go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Reflect)]
+pub enum Surface {
+    #[default]
+    Stone,
+    Water,
+    Grass,
+}
+
+impl Surface {
+    /// Reads a [`SURFACE_PROPERTY`] value, e.g. `"water"`.
+    pub fn from_property(value: &PropertyValue) -> Option<Self> {
+        let PropertyValue::StringValue(name) = value else {
+            return None;
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "stone" => Some(Surface::Stone),
+            "water" => Some(Surface::Water),
+            "grass" => Some(Surface::Grass),
+            _ => None,
+        }
+    }
+}
+
+/// The sounds of a [`Surface`], and how they are played.
+#[derive(Debug, Clone)]
+pub struct SoundBank {
+    pub sounds: Vec<Handle<AudioSource>>,
+    /// Playback speed, which also sets the pitch.
+    pub speed: f32,
+    /// Linear volume.
+    pub volume: f32,
+    /// Each step picks a speed and a volume up to this fraction away from the ones above.
+    pub variation: f32,
+}
+
+impl SoundBank {
+    /// A random sound other than the `last` one, and the settings to play it with. Banks share
+    /// sounds, so the last one may come from another bank.
+    fn pick(
+        &self,
+        last: Option<AssetId<AudioSource>>,
+        rng: &mut impl Rng,
+    ) -> Option<(Handle<AudioSource>, PlaybackSettings)> {
+        let others: Vec<_> = self
+            .sounds
+            .iter()
+            .filter(|sound| Some(sound.id()) != last)
+            .collect();
+        let sound = match others.choose(rng) {
+            Some(&sound) => sound.clone(),
+            None => self.sounds.choose(rng)?.clone(),
+        };
+        let mut vary =
+            |value: f32| value * (1.0 + rng.random_range(-1.0f32..=1.0) * self.variation);
+        let settings = PlaybackSettings::DESPAWN
+            .with_speed(vary(self.speed))
+            .with_volume(Volume::Linear(vary(self.volume)));
+        Some((sound, settings))
+    }
+}
+
+/// The last footstep sound of a character, so that it doesn't play twice in a row.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct LastFootstep(pub AssetId<AudioSource>);
+
+/// There are only stone recordings for now: water and grass reuse them, lower and softer.
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct FootstepAssets {
+    #[dependency]
+    stone: Vec<Handle<AudioSource>>,
+    #[dependency]
+    water: Vec<Handle<AudioSource>>,
+    #[dependency]
+    grass: Vec<Handle<AudioSource>>,
+}
+
+impl FootstepAssets {
+    pub fn bank(&self, surface: Surface) -> SoundBank {
+        let (sounds, speed, volume) = match surface {
+            Surface::Stone => (&self.stone, 1.0, 1.0),
+            Surface::Water => (&self.water, 0.75, 0.9),
+            Surface::Grass => (&self.grass, 1.2, 0.5),
+        };
+        SoundBank {
+            sounds: sounds.clone(),
+            speed,
+            volume,
+            variation: 0.1,
+        }
+    }
+}
+
+impl FromWorld for FootstepAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        let steps: Vec<Handle<AudioSource>> = (1..=4)
+            .map(|i| assets.load(format!("audio/sound_effects/step{i}.ogg")))
+            .collect();
+        Self {
+            stone: steps.clone(),
+            water: steps.clone(),
+            grass: steps,
+        }
+    }
+}
+
+/// The surface under `position`, on the first map that has one there.
+fn surface_at(
+    tiled_query: &TiledMapQuery,
+    map_query: &Query<Entity, With<TiledMapHandle>>,
+    position: Vec2,
+) -> Surface {
+    map_query
+        .iter()
+        .find_map(|map| tiled_query.property_at(map, position, SURFACE_PROPERTY))
+        .and_then(|value| Surface::from_property(&value))
+        .unwrap_or_default()
+}
+
+fn play_footstep(
+    event: On<AnimationEvent>,
+    mut commands: Commands,
+    footstep_assets: If<Res<FootstepAssets>>,
+    tiled_query: TiledMapQuery,
+    map_query: Query<Entity, With<TiledMapHandle>>,
+    transform_query: Query<(&GlobalTransform, Option<&LastFootstep>)>,
+    player: Option<Single<(Entity, &GlobalTransform), With<Player>>>,
+) {
+    if event.name != STEP_EVENT {
+        return;
+    }
+    let Ok((transform, last)) = transform_query.get(event.entity) else {
+        return;
+    };
+    let position = transform.translation().truncate();
+    // Fades out linearly with the distance to the player, who hears their own steps in full.
+    let gain = match player.map(|player| player.into_inner()) {
+        Some((player, _)) if player == event.entity => 1.0,
+        Some((_, player_transform)) => {
+            let distance = position.distance(player_transform.translation().truncate());
+            1.0 - distance / FOOTSTEP_HEARING_RANGE
+        }
+        None => 0.0,
+    };
+    if gain <= 0.0 {
+        return;
+    }
+
+    let surface = surface_at(&tiled_query, &map_query, position);
+    let bank = footstep_assets.bank(surface);
+    let rng = &mut rand::rng();
+    let Some((sound, mut settings)) = bank.pick(last.map(|last| last.0), rng) else {
+        return;
+    };
+    commands
+        .entity(event.entity)
+        .insert(LastFootstep(sound.id()));
+    settings.volume = Volume::Linear(settings.volume.to_linear() * gain);
+    commands.spawn(sound_effect_with_settings(sound, settings));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::uuid::Uuid;
+
+    use super::*;
+
+    fn bank(sounds: &[Handle<AudioSource>]) -> SoundBank {
+        SoundBank {
+            sounds: sounds.to_vec(),
+            speed: 1.0,
+            volume: 1.0,
+            variation: 0.1,
+        }
+    }
+
+    #[test]
+    fn the_last_sound_is_not_picked_again_whatever_its_bank() {
+        let sounds: Vec<Handle<AudioSource>> = (0..4)
+            .map(|i| Handle::Uuid(Uuid::from_u128(i), default()))
+            .collect();
+        // Two banks sharing the same sounds, like the surfaces do.
+        let (stone, water) = (bank(&sounds), bank(&sounds));
+        let rng = &mut StdRng::seed_from_u64(0);
+
+        let mut last = None;
+        for step in 0..100 {
+            let bank = if step % 3 == 0 { &water } else { &stone };
+            let (sound, _) = bank.pick(last, rng).unwrap();
+            assert_ne!(Some(sound.id()), last);
+            last = Some(sound.id());
+        }
+    }
+
+    #[test]
+    fn single_sound_banks_repeat_it() {
+        let sound = Handle::Uuid(Uuid::from_u128(1), default());
+        let (picked, _) = bank(std::slice::from_ref(&sound))
+            .pick(Some(sound.id()), &mut StdRng::seed_from_u64(0))
+            .unwrap();
+        assert_eq!(picked, sound);
+        assert!(
+            bank(&[])
+                .pick(None, &mut StdRng::seed_from_u64(0))
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,380 @@
+//! Health, damage and death.
+//!
+//! Entities with a [`Damage`] collider hurt the characters with [`Health`] touching them, and so do
+//! tiles or layers with a [`DAMAGE_PROPERTY`] in Tiled. A hurt character is knocked back, flashes
+//! and can't be hurt again until its [`Invulnerable`] ends. The game is over when the player dies,
+//! once their [`DEATH`] clip has played.
+
+use std::time::Duration;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use tiled::PropertyValue;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        animation::{DEATH, HURT},
+        movement::Invulnerable,
+        player::Player,
+    },
+    screens::Screen,
+    theme::widget,
+    utils::{
+        animator::{AnimationFinished, Animator},
+        cam::{KickCamera, ShakeCamera},
+        tiled::{TiledMapHandle, query::TiledMapQuery},
+    },
+};
+
+/// The custom property of tiles and layers hurting whoever stands on them, by that much.
+pub const DAMAGE_PROPERTY: &str = "damage";
+/// How long a hurt character can't be hurt again.
+pub const HURT_INVULNERABILITY: Duration = Duration::from_millis(1000);
+/// Speed a hurt character is pushed away from what hurt it at, in world units per second.
+pub const KNOCKBACK_SPEED: f32 = 300.0;
+/// How many times per second a hurt character blinks.
+const FLASH_RATE: f32 = 10.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        (hurt_on_contact, hurt_on_hazard_tiles)
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+    app.add_systems(
+        Update,
+        flash_hurt_sprites
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+    app.add_systems(OnEnter(Screen::Gameplay), spawn_health_hud);
+    app.add_systems(Update, update_health_hud.run_if(in_state(Screen::Gameplay)));
+    app.add_observer(apply_hurt);
+    app.add_observer(stop_flashing);
+    app.add_observer(play_death);
+    app.add_observer(handle_death);
+}
+
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Health {
+    pub fn new(max: u32) -> Self {
+        Self { current: max, max }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current == 0
+    }
+}
+
+/// Hurts the characters with [`Health`] touching this collider, or the colliders of this body.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct Damage {
+    pub amount: u32,
+}
+
+/// Hurts a character with [`Health`], unless it is [`Invulnerable`].
+#[derive(EntityEvent, Debug, Clone, Copy)]
+pub struct Hurt {
+    pub entity: Entity,
+    pub amount: u32,
+    /// Where the hit came from, to knock the character away from it.
+    pub source: Option<Vec2>,
+}
+
+/// Sent when the [`Health`] of a character runs out.
+#[derive(EntityEvent, Debug, Clone, Copy)]
+pub struct Died {
+    pub entity: Entity,
+}
+
+/// Blinks the sprite while the character is [`Invulnerable`] from a hit.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct Flashing;
+
+fn hurt_on_contact(
+    mut commands: Commands,
+    health_query: Query<(Entity, &CollidingEntities), (With<Health>, Without<Invulnerable>)>,
+    damage_query: Query<(&Damage, &GlobalTransform)>,
+    collider_query: Query<&ColliderOf>,
+) {
+    for (entity, colliding) in &health_query {
+        let hit = colliding.iter().find_map(|&other| {
+            damage_query.get(other).ok().or_else(|| {
+                let body = collider_query.get(other).ok()?.body;
+                damage_query.get(body).ok()
+            })
+        });
+        if let Some((damage, transform)) = hit {
+            commands.trigger(Hurt {
+                entity,
+                amount: damage.amount,
+                source: Some(transform.translation().truncate()),
+            });
+        }
+    }
+}
+
+fn hurt_on_hazard_tiles(
+    mut commands: Commands,
+    tiled_query: TiledMapQuery,
+    map_query: Query<Entity, With<TiledMapHandle>>,
+    health_query: Query<(Entity, &GlobalTransform), (With<Health>, Without<Invulnerable>)>,
+) {
+    for (entity, transform) in &health_query {
+        let position = transform.translation().truncate();
+        let damage = map_query
+            .iter()
+            .find_map(|map| tiled_query.property_at(map, position, DAMAGE_PROPERTY));
+        if let Some(PropertyValue::IntValue(amount)) = damage
+            && amount > 0
+        {
+            commands.trigger(Hurt {
+                entity,
+                amount: amount as u32,
+                source: None,
+            });
+        }
+    }
+}
+
+fn apply_hurt(
+    hurt: On<Hurt>,
+    mut commands: Commands,
+    mut health_query: Query<
+        (
+            &mut Health,
+            &GlobalTransform,
+            Option<&mut LinearVelocity>,
+            Option<&mut Animator>,
+        ),
+        Without<Invulnerable>,
+    >,
+) {
+    let Ok((mut health, transform, velocity, animator)) = health_query.get_mut(hurt.entity) else {
+        return;
+    };
+    if health.is_dead() {
+        return;
+    }
+    health.current = health.current.saturating_sub(hurt.amount);
+    if health.is_dead() {
+        commands.trigger(Died {
+            entity: hurt.entity,
+        });
+        return;
+    }
+
+    commands
+        .entity(hurt.entity)
+        .insert((Invulnerable::new(HURT_INVULNERABILITY), Flashing));
+    if let Some(mut animator) = animator {
+        animator.play_once(HURT);
+    }
+    let away = hurt
+        .source
+        .and_then(|source| (transform.translation().truncate() - source).try_normalize());
+    if let (Some(away), Some(mut velocity)) = (away, velocity) {
+        // Movement steers back from this at its deceleration.
+        velocity.0 = away * KNOCKBACK_SPEED;
+        commands.trigger(KickCamera {
+            impulse: away * 6.0,
+        });
+    }
+    commands.trigger(ShakeCamera { trauma: 0.4 });
+}
+
+fn flash_hurt_sprites(mut flashing_query: Query<(&Invulnerable, &mut Sprite), With<Flashing>>) {
+    for (invulnerable, mut sprite) in &mut flashing_query {
+        let blink = (invulnerable.timer.elapsed_secs() * FLASH_RATE * 2.0) as u32 % 2 == 0;
+        sprite.color.set_alpha(if blink { 0.25 } else { 1.0 });
+    }
+}
+
+fn stop_flashing(
+    remove: On<Remove, Invulnerable>,
+    mut commands: Commands,
+    mut sprite_query: Query<&mut Sprite, With<Flashing>>,
+) {
+    let Ok(mut sprite) = sprite_query.get_mut(remove.entity) else {
+        return;
+    };
+    sprite.color.set_alpha(1.0);
+    commands.entity(remove.entity).try_remove::<Flashing>();
+}
+
+/// Plays the death clip of the character. Characters without an [`Animator`] are done at once.
+fn play_death(died: On<Died>, mut commands: Commands, mut animator_query: Query<&mut Animator>) {
+    match animator_query.get_mut(died.entity) {
+        Ok(mut animator) => animator.play_once(DEATH),
+        Err(_) => commands.trigger(AnimationFinished {
+            entity: died.entity,
+            state: DEATH.to_string(),
+        }),
+    }
+}
+
+/// The game is over when the player is done dying. Other characters just go away.
+fn handle_death(
+    finished: On<AnimationFinished>,
+    mut commands: Commands,
+    player_query: Query<(), With<Player>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    if finished.state != DEATH {
+        return;
+    }
+    if player_query.contains(finished.entity) {
+        next_screen.set(Screen::GameOver);
+    } else {
+        commands.entity(finished.entity).despawn();
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct HealthLabel;
+
+fn spawn_health_hud(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Health HUD"),
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(12),
+            right: px(12),
+            ..default()
+        },
+        Pickable::IGNORE,
+        DespawnOnExit(Screen::Gameplay),
+        children![(widget::label(""), HealthLabel)],
+    ));
+}
+
+fn update_health_hud(
+    health: Single<&Health, (With<Player>, Changed<Health>)>,
+    mut label: Single<&mut Text, With<HealthLabel>>,
+) {
+    label.0 = format!("HP {}/{}", health.current, health.max);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use bevy::{ecs::system::RunSystemOnce, state::app::StatesPlugin};
+
+    use super::*;
+    use crate::utils::tiled::testing::load_map;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .init_state::<Screen>()
+            .add_observer(apply_hurt)
+            .add_observer(play_death)
+            .add_observer(handle_death);
+        app
+    }
+
+    fn health(app: &App, entity: Entity) -> u32 {
+        app.world().get::<Health>(entity).unwrap().current
+    }
+
+    #[test]
+    fn contact_damage_skips_invulnerable_characters() {
+        let mut app = app();
+        let world = app.world_mut();
+        let hazard = world
+            .spawn((
+                Damage { amount: 1 },
+                GlobalTransform::from_xyz(-10.0, 0.0, 0.0),
+            ))
+            .id();
+        let mut colliding = CollidingEntities::default();
+        colliding.insert(hazard);
+        let character = world
+            .spawn((
+                Health::new(3),
+                colliding,
+                GlobalTransform::default(),
+                LinearVelocity::default(),
+            ))
+            .id();
+
+        world.run_system_once(hurt_on_contact).unwrap();
+        assert_eq!(health(&app, character), 2);
+        assert!(app.world().get::<Invulnerable>(character).is_some());
+        // Knocked away from the hazard.
+        assert_eq!(
+            app.world().get::<LinearVelocity>(character).unwrap().0,
+            Vec2::X * KNOCKBACK_SPEED
+        );
+
+        app.world_mut().run_system_once(hurt_on_contact).unwrap();
+        assert_eq!(health(&app, character), 2);
+
+        app.world_mut()
+            .entity_mut(character)
+            .remove::<Invulnerable>();
+        app.world_mut().run_system_once(hurt_on_contact).unwrap();
+        assert_eq!(health(&app, character), 1);
+    }
+
+    #[test]
+    fn the_game_is_over_when_the_player_dies() {
+        let mut app = app();
+        let world = app.world_mut();
+        let player = world
+            .spawn((Player, Health::new(2), GlobalTransform::default()))
+            .id();
+        let other = world
+            .spawn((Health::new(1), GlobalTransform::default()))
+            .id();
+
+        world.trigger(Hurt {
+            entity: other,
+            amount: 1,
+            source: None,
+        });
+        assert!(app.world().get_entity(other).is_err());
+
+        app.world_mut().trigger(Hurt {
+            entity: player,
+            amount: 5,
+            source: None,
+        });
+        app.update();
+        assert_eq!(health(&app, player), 0);
+        assert_eq!(
+            *app.world().resource::<State<Screen>>().get(),
+            Screen::GameOver
+        );
+    }
+
+    #[test]
+    fn water_in_the_first_map_hurts() {
+        let path = "tiled/map1.tile-16x16.tmx";
+        let bytes = std::fs::read(Path::new("assets").join(path)).unwrap();
+        let map = load_map(&bytes, path).map;
+        let water = map
+            .layers()
+            .find(|layer| layer.name == "Water")
+            .expect("map1 should have a Water layer");
+        assert!(
+            matches!(
+                water.properties.get(DAMAGE_PROPERTY),
+                Some(PropertyValue::IntValue(damage)) if *damage > 0
+            ),
+            "the Water layer should have a positive int `{DAMAGE_PROPERTY}` property"
+        );
+    }
+}
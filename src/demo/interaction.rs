@@ -0,0 +1,150 @@
+//! Things the player can interact with: switches, signs, chests...
+//!
+//! The closest [`Interactable`] in range that the player faces has the focus, and its prompt is
+//! shown at the bottom of the screen. Pressing [`Action::Interact`] then triggers [`Interact`] on it.
+
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{movement::MovementController, player::Player},
+    input::{Action, InputBindings, action_just_pressed},
+    screens::Screen,
+    theme::widget,
+};
+
+/// How far from where the player faces an interactable can be, as the cosine of the angle.
+pub const FACING_COS: f32 = 0.5;
+/// Interactables closer than this count as faced whatever the direction, e.g. under the feet.
+pub const UNDERFOOT_DISTANCE: f32 = 8.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<InteractionFocus>();
+    app.add_systems(
+        Update,
+        (
+            interact
+                .run_if(action_just_pressed(Action::Interact))
+                .in_set(AppSystems::RecordInput),
+            update_interaction_focus.in_set(AppSystems::Update),
+        )
+            .in_set(PausableSystems),
+    );
+    app.add_systems(OnEnter(Screen::Gameplay), spawn_interaction_prompt);
+    app.add_systems(
+        Update,
+        update_interaction_prompt.run_if(
+            in_state(Screen::Gameplay)
+                .and(resource_changed::<InteractionFocus>.or(resource_changed::<InputBindings>)),
+        ),
+    );
+}
+
+/// Something the player can interact with.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Interactable {
+    /// How close the player has to be, in world units.
+    pub range: f32,
+    /// What interacting does, e.g. "Open".
+    pub prompt: String,
+}
+
+impl Interactable {
+    pub fn new(range: f32, prompt: impl Into<String>) -> Self {
+        Self {
+            range,
+            prompt: prompt.into(),
+        }
+    }
+}
+
+/// Sent to the focused [`Interactable`] when the player interacts with it.
+#[derive(EntityEvent, Debug, Clone, Copy)]
+pub struct Interact {
+    pub entity: Entity,
+    /// Who interacted, i.e. the player.
+    pub interactor: Entity,
+}
+
+/// The [`Interactable`] that the player would interact with.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct InteractionFocus(pub Option<Entity>);
+
+fn update_interaction_focus(
+    mut focus: ResMut<InteractionFocus>,
+    player: Option<Single<(&GlobalTransform, &MovementController), With<Player>>>,
+    interactable_query: Query<(Entity, &Interactable, &GlobalTransform)>,
+) {
+    let focused = player.and_then(|player| {
+        let (transform, controller) = player.into_inner();
+        let position = transform.translation().truncate();
+        interactable_query
+            .iter()
+            .filter_map(|(entity, interactable, target)| {
+                let offset = target.translation().truncate() - position;
+                let distance = offset.length();
+                let faced = distance <= UNDERFOOT_DISTANCE
+                    || offset.normalize().dot(controller.facing) >= FACING_COS;
+                (distance <= interactable.range && faced).then_some((entity, distance))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(entity, _)| entity)
+    });
+    focus.set_if_neq(InteractionFocus(focused));
+}
+
+fn interact(
+    mut commands: Commands,
+    focus: Res<InteractionFocus>,
+    player: Single<Entity, With<Player>>,
+) {
+    if let Some(entity) = focus.0 {
+        commands.trigger(Interact {
+            entity,
+            interactor: *player,
+        });
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct InteractionPrompt;
+
+fn spawn_interaction_prompt(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Interaction Prompt"),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: px(12),
+            width: percent(100),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        Pickable::IGNORE,
+        DespawnOnExit(Screen::Gameplay),
+        children![(widget::label(""), InteractionPrompt, Visibility::Hidden)],
+    ));
+}
+
+fn update_interaction_prompt(
+    focus: Res<InteractionFocus>,
+    bindings: Res<InputBindings>,
+    interactable_query: Query<&Interactable>,
+    prompt: Single<(&mut Text, &mut Visibility), With<InteractionPrompt>>,
+) {
+    let (mut text, mut visibility) = prompt.into_inner();
+    let Some(interactable) = focus
+        .0
+        .and_then(|entity| interactable_query.get(entity).ok())
+    else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    text.0 = match bindings.get(Action::Interact).first() {
+        Some(binding) => format!("[{binding}] {}", interactable.prompt),
+        None => interactable.prompt.clone(),
+    };
+    *visibility = Visibility::Inherited;
+}
@@ -7,6 +7,7 @@ use crate::{
     audio::music,
     demo::player::{PlayerAssets, player},
     screens::Screen,
+    utils::animation_set::AnimationSet,
 };
 
 pub(super) fn plugin(app: &mut App) {
@@ -36,7 +37,7 @@ pub fn spawn_level(
     mut commands: Commands,
     level_assets: Res<LevelAssets>,
     player_assets: Res<PlayerAssets>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    animation_sets: Res<Assets<AnimationSet>>,
 ) {
     commands.spawn((
         Name::new("Level"),
@@ -44,7 +45,7 @@ pub fn spawn_level(
         Visibility::default(),
         DespawnOnExit(Screen::Gameplay),
         children![
-            player(100.0, &player_assets, &mut texture_atlas_layouts),
+            player(100.0, &player_assets, &animation_sets),
             (
                 Name::new("Gameplay Music"),
                 music(level_assets.music.clone())
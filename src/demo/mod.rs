@@ -6,15 +6,29 @@
 use bevy::prelude::*;
 
 mod animation;
+mod collectibles;
+mod footsteps;
+mod health;
+mod interaction;
 pub mod level;
 mod movement;
+mod npc;
 pub mod player;
+mod props;
+mod switches;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
         animation::plugin,
+        collectibles::plugin,
+        footsteps::plugin,
+        health::plugin,
+        interaction::plugin,
         level::plugin,
         movement::plugin,
+        npc::plugin,
         player::plugin,
+        props::plugin,
+        switches::plugin,
     ));
 }
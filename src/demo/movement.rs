@@ -3,29 +3,34 @@
 //! the movement of characters.
 //!
 //! In our case, the character controller has the following logic:
-//! - Set [`MovementController`] intent based on directional keyboard input.
+//! - Set [`MovementController`] intent based on directional keyboard and gamepad input.
 //!   This is done in the `player` module, as it is specific to the player
 //!   character.
-//! - Apply movement based on [`MovementController`] intent and maximum speed.
-//! - Wrap the character within the window.
+//! - Apply movement based on [`MovementController`] intent, maximum speed, acceleration and
+//!   turn rate, or the current [`Dash`].
 //!
-//! Note that the implementation used here is limited for demonstration
-//! purposes. If you want to move the player in a smoother way,
-//! consider using a [fixed timestep](https://github.com/bevyengine/bevy/blob/main/examples/movement/physics_in_fixed_timestep.rs).
+//! Movement runs in `FixedUpdate`, right before the physics step, so it doesn't depend on the
+//! frame rate. Moving entities should have a [`TransformInterpolation`] so they are drawn
+//! smoothly in between steps, see the
+//! [fixed timestep example](https://github.com/bevyengine/bevy/blob/main/examples/movement/physics_in_fixed_timestep.rs).
 
-use crate::{AppSystems, PausableSystems, demo::animation::Direction};
+use std::time::Duration;
+
+use crate::{AppSystems, PausableSystems};
 use avian2d::prelude::*;
 use bevy::prelude::*;
 //window::PrimaryWindow
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(
-        Update,
-        (apply_movement)
-            .chain()
-            .in_set(AppSystems::Update)
+        FixedUpdate,
+        (
+            (tick_dash_timers, tick_invulnerability).in_set(AppSystems::TickTimers),
+            apply_movement.in_set(AppSystems::Update),
+        )
             .in_set(PausableSystems),
     );
+    app.add_observer(start_dash);
 }
 
 /// These are the movement parameters for our character controller.
@@ -34,39 +39,430 @@ pub(super) fn plugin(app: &mut App) {
 #[derive(Component, Reflect)]
 #[reflect(Component)]
 pub struct MovementController {
-    /// The direction the character wants to move in.
-    //pub intent: Vec2,
-    pub intent: Direction,
+    /// The direction the character wants to move in, with a length between 0 (standing still)
+    /// and 1 (full speed), e.g. from a partially tilted stick.
+    pub intent: Vec2,
 
     /// Maximum speed in world units per second.
     /// 1 world unit = 1 pixel when using the default 2D camera and no physics engine.
     pub max_speed: f32,
+
+    /// How fast the character speeds up, in world units per second squared.
+    /// [`f32::INFINITY`] reaches the wanted speed at once.
+    pub acceleration: f32,
+
+    /// How fast the character slows down, in world units per second squared.
+    pub deceleration: f32,
+
+    /// How fast the character changes direction, in radians per second.
+    pub turn_rate: f32,
+
+    /// The last direction the character moved in. Dashes go this way when there is no intent.
+    pub facing: Vec2,
 }
 
 impl Default for MovementController {
     fn default() -> Self {
         Self {
-            //intent: Vec2::ZERO,
-            intent: Direction::Nothing,
+            intent: Vec2::ZERO,
             max_speed: 1.0,
+            acceleration: f32::INFINITY,
+            deceleration: f32::INFINITY,
+            turn_rate: f32::INFINITY,
+            facing: Vec2::NEG_Y,
+        }
+    }
+}
+
+impl MovementController {
+    /// The velocity after `dt` seconds of steering from `velocity` towards the intent.
+    fn steer(&self, velocity: Vec2, dt: f32) -> Vec2 {
+        // An infinite rate times a zero delta would be NaN.
+        if dt <= 0.0 {
+            return velocity;
+        }
+        let target = self.max_speed * self.intent.clamp_length_max(1.0);
+        let speed = velocity.length();
+        let target_speed = target.length();
+
+        let direction = if speed > f32::EPSILON {
+            velocity / speed
+        } else if target_speed > f32::EPSILON {
+            return target.clamp_length_max(self.acceleration * dt);
+        } else {
+            return Vec2::ZERO;
+        };
+        let direction = if target_speed > f32::EPSILON {
+            let max_turn = self.turn_rate * dt;
+            let angle = direction.angle_to(target / target_speed);
+            Rot2::radians(angle.clamp(-max_turn, max_turn)) * direction
+        } else {
+            direction
+        };
+
+        let rate = if target_speed > speed {
+            self.acceleration
+        } else {
+            self.deceleration
+        };
+        direction * move_towards(speed, target_speed, rate * dt)
+    }
+}
+
+fn move_towards(current: f32, target: f32, max_delta: f32) -> f32 {
+    current + (target - current).clamp(-max_delta, max_delta)
+}
+
+/// A quick burst of speed with a cooldown, during which the character can't be hurt for a
+/// while. Start it with [`StartDash`].
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Dash {
+    /// Speed during the dash, in world units per second.
+    pub speed: f32,
+    /// How long the character can't be hurt from the start of the dash.
+    pub invulnerability: Duration,
+    dash_timer: Timer,
+    cooldown_timer: Timer,
+    direction: Vec2,
+}
+
+impl Dash {
+    pub fn new(
+        speed: f32,
+        duration: Duration,
+        cooldown: Duration,
+        invulnerability: Duration,
+    ) -> Self {
+        let mut dash_timer = Timer::new(duration, TimerMode::Once);
+        dash_timer.finish();
+        let mut cooldown_timer = Timer::new(cooldown, TimerMode::Once);
+        cooldown_timer.finish();
+        Self {
+            speed,
+            invulnerability,
+            dash_timer,
+            cooldown_timer,
+            direction: Vec2::ZERO,
+        }
+    }
+
+    pub fn is_dashing(&self) -> bool {
+        !self.dash_timer.is_finished()
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.cooldown_timer.is_finished()
+    }
+}
+
+/// Makes an entity with a [`Dash`] dash, if its cooldown is over.
+#[derive(EntityEvent, Debug, Clone, Copy)]
+pub struct StartDash {
+    pub entity: Entity,
+}
+
+/// The entity can't be hurt until the timer finishes.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Invulnerable {
+    pub timer: Timer,
+}
+
+impl Invulnerable {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            timer: Timer::new(duration, TimerMode::Once),
+        }
+    }
+}
+
+fn start_dash(
+    dash: On<StartDash>,
+    mut commands: Commands,
+    mut dash_query: Query<(&mut Dash, &MovementController, Option<&Invulnerable>)>,
+) {
+    let Ok((mut dash_state, controller, invulnerable)) = dash_query.get_mut(dash.entity) else {
+        return;
+    };
+    if !dash_state.is_ready() {
+        return;
+    }
+    dash_state.direction = controller
+        .intent
+        .try_normalize()
+        .unwrap_or(controller.facing);
+    dash_state.dash_timer.reset();
+    dash_state.cooldown_timer.reset();
+
+    // Don't cut a longer invulnerability short.
+    if invulnerable.is_none_or(|i| i.timer.remaining() < dash_state.invulnerability) {
+        commands
+            .entity(dash.entity)
+            .insert(Invulnerable::new(dash_state.invulnerability));
+    }
+}
+
+fn tick_dash_timers(time: Res<Time>, mut dash_query: Query<&mut Dash>) {
+    for mut dash in &mut dash_query {
+        dash.dash_timer.tick(time.delta());
+        dash.cooldown_timer.tick(time.delta());
+    }
+}
+
+fn tick_invulnerability(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut invulnerable_query: Query<(Entity, &mut Invulnerable)>,
+) {
+    for (entity, mut invulnerable) in &mut invulnerable_query {
+        if invulnerable.timer.tick(time.delta()).is_finished() {
+            commands.entity(entity).remove::<Invulnerable>();
         }
     }
 }
 
-/*
 fn apply_movement(
     time: Res<Time>,
-    mut movement_query: Query<(&MovementController, &mut Transform)>,
+    mut movement_query: Query<(&mut MovementController, Option<&Dash>, &mut LinearVelocity)>,
 ) {
-    for (controller, mut transform) in &mut movement_query {
-        let velocity = controller.max_speed * controller.intent;
-        transform.translation += velocity.extend(0.0) * time.delta_secs();
+    for (mut controller, dash, mut rb_vel) in movement_query.iter_mut() {
+        if let Some(direction) = controller.intent.try_normalize() {
+            controller.facing = direction;
+        }
+        rb_vel.0 = match dash {
+            Some(dash) if dash.is_dashing() => dash.direction * dash.speed,
+            _ => controller.steer(rb_vel.0, time.delta_secs()),
+        };
     }
 }
-*/
 
-fn apply_movement(mut movement_query: Query<(&MovementController, &mut LinearVelocity)>) {
-    for (controller, mut rb_vel) in movement_query.iter_mut() {
-        rb_vel.0 = controller.max_speed * controller.intent.get_vec2(); // normal
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use bevy::{ecs::system::RunSystemOnce, time::TimeUpdateStrategy};
+
+    use super::*;
+
+    /// How long each run is simulated for.
+    const SIMULATED: Duration = Duration::from_secs(2);
+
+    /// Turns around halfway, so that turning and braking are covered too.
+    fn steer_back(time: Res<Time>, mut controller_query: Query<&mut MovementController>) {
+        if time.elapsed() >= SIMULATED / 2 {
+            for mut controller in &mut controller_query {
+                controller.intent = Vec2::new(-1.0, -0.5);
+            }
+        }
+    }
+
+    /// Where a character ends up after [`SIMULATED`], rendering `frame_rate` frames per second.
+    fn simulate(frame_rate: u32) -> Vec2 {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            AssetPlugin::default(),
+            PhysicsPlugins::default(),
+            plugin,
+        ));
+        app.init_asset::<Mesh>();
+        app.configure_sets(
+            FixedUpdate,
+            (
+                AppSystems::TickTimers,
+                AppSystems::RecordInput,
+                AppSystems::Update,
+            )
+                .chain(),
+        );
+        app.add_systems(FixedUpdate, steer_back.in_set(AppSystems::RecordInput));
+        let character = app
+            .world_mut()
+            .spawn((
+                MovementController {
+                    intent: Vec2::new(1.0, 0.5),
+                    max_speed: 100.0,
+                    acceleration: 400.0,
+                    deceleration: 600.0,
+                    turn_rate: 6.0,
+                    ..default()
+                },
+                RigidBody::Kinematic,
+                Transform::default(),
+            ))
+            .id();
+
+        // The first update only starts the clock.
+        app.update();
+        let frame = Duration::from_secs(1) / frame_rate;
+        let mut elapsed = Duration::ZERO;
+        while elapsed < SIMULATED {
+            let delta = frame.min(SIMULATED - elapsed);
+            app.insert_resource(TimeUpdateStrategy::ManualDuration(delta));
+            app.update();
+            elapsed += delta;
+        }
+        assert_eq!(app.world().resource::<Time<Virtual>>().elapsed(), SIMULATED);
+
+        app.world()
+            .get::<Transform>(character)
+            .unwrap()
+            .translation
+            .truncate()
+    }
+
+    #[test]
+    fn movement_does_not_depend_on_the_frame_rate() {
+        let at_30 = simulate(30);
+        let at_144 = simulate(144);
+        assert!(at_30.length() > 10.0, "the character should move: {at_30}");
+        assert_eq!(at_30, at_144);
+    }
+
+    fn controller(intent: Vec2) -> MovementController {
+        MovementController {
+            intent,
+            max_speed: 100.0,
+            acceleration: 400.0,
+            deceleration: 600.0,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn steering_speeds_up_and_slows_down_at_its_rates() {
+        let moving = controller(Vec2::X);
+        assert_eq!(moving.steer(Vec2::ZERO, 0.1), Vec2::new(40.0, 0.0));
+        assert_eq!(
+            moving.steer(Vec2::new(80.0, 0.0), 0.1),
+            Vec2::new(100.0, 0.0)
+        );
+
+        let stopping = controller(Vec2::ZERO);
+        assert_eq!(
+            stopping.steer(Vec2::new(100.0, 0.0), 0.1),
+            Vec2::new(40.0, 0.0)
+        );
+        assert_eq!(stopping.steer(Vec2::new(40.0, 0.0), 0.1), Vec2::ZERO);
+
+        // Going faster than the intent, e.g. after a dash, brakes.
+        let slow = controller(Vec2::X * 0.5);
+        assert_eq!(
+            slow.steer(Vec2::new(100.0, 0.0), 0.05),
+            Vec2::new(70.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn infinite_rates_reach_the_intent_at_once() {
+        let controller = MovementController {
+            intent: Vec2::new(3.0, 4.0),
+            max_speed: 100.0,
+            ..default()
+        };
+        let target = Vec2::new(60.0, 80.0);
+        for velocity in [Vec2::ZERO, Vec2::new(-100.0, 0.0)] {
+            let steered = controller.steer(velocity, 1.0 / 64.0);
+            assert!(steered.abs_diff_eq(target, 1e-3), "{steered}");
+        }
+    }
+
+    #[test]
+    fn steering_turns_at_the_turn_rate() {
+        let controller = MovementController {
+            turn_rate: FRAC_PI_2,
+            ..controller(Vec2::Y)
+        };
+        // A quarter turn at a quarter turn per second, half a second at a time.
+        let halfway = controller.steer(Vec2::new(100.0, 0.0), 0.5);
+        assert!(
+            halfway.abs_diff_eq(Vec2::from_angle(FRAC_PI_2 / 2.0) * 100.0, 1e-3),
+            "{halfway}"
+        );
+        let there = controller.steer(halfway, 0.5);
+        assert!(there.abs_diff_eq(Vec2::new(0.0, 100.0), 1e-3), "{there}");
+        // It doesn't turn past the intent.
+        assert!(controller.steer(there, 0.5).abs_diff_eq(there, 1e-3));
+    }
+
+    #[test]
+    fn steering_without_time_keeps_the_velocity() {
+        // Infinite rates would make NaN out of a zero delta.
+        let controller = MovementController {
+            intent: Vec2::X,
+            max_speed: 100.0,
+            ..default()
+        };
+        let velocity = Vec2::new(0.0, 50.0);
+        assert_eq!(controller.steer(velocity, 0.0), velocity);
+        assert_eq!(controller.steer(velocity, -0.1), velocity);
+    }
+
+    const DASH_INVULNERABILITY: Duration = Duration::from_millis(200);
+
+    fn spawn_dasher(world: &mut World) -> Entity {
+        world.init_resource::<Time>();
+        world.add_observer(start_dash);
+        world
+            .spawn((
+                Dash::new(
+                    300.0,
+                    Duration::from_millis(100),
+                    Duration::from_millis(500),
+                    DASH_INVULNERABILITY,
+                ),
+                MovementController::default(),
+            ))
+            .id()
+    }
+
+    fn elapse(world: &mut World, duration: Duration) {
+        world.resource_mut::<Time>().advance_by(duration);
+        world.run_system_once(tick_dash_timers).unwrap();
+        world.run_system_once(tick_invulnerability).unwrap();
+    }
+
+    fn dash(world: &mut World, entity: Entity) -> &Dash {
+        world.trigger(StartDash { entity });
+        world.get::<Dash>(entity).unwrap()
+    }
+
+    #[test]
+    fn dashes_wait_for_their_cooldown() {
+        let mut world = World::new();
+        let dasher = spawn_dasher(&mut world);
+
+        let first = dash(&mut world, dasher);
+        assert!(first.is_dashing() && !first.is_ready());
+        // Without an intent, it goes the way the character faces.
+        assert_eq!(first.direction, Vec2::NEG_Y);
+        assert!(world.get::<Invulnerable>(dasher).is_some());
+
+        elapse(&mut world, Duration::from_millis(250));
+        assert!(world.get::<Invulnerable>(dasher).is_none());
+        assert!(!dash(&mut world, dasher).is_dashing());
+
+        elapse(&mut world, Duration::from_millis(250));
+        assert!(dash(&mut world, dasher).is_dashing());
+    }
+
+    #[test]
+    fn dashes_do_not_cut_a_longer_invulnerability_short() {
+        let mut world = World::new();
+        let dasher = spawn_dasher(&mut world);
+        let remaining =
+            |world: &World| world.get::<Invulnerable>(dasher).unwrap().timer.remaining();
+
+        world
+            .entity_mut(dasher)
+            .insert(Invulnerable::new(Duration::from_secs(1)));
+        dash(&mut world, dasher);
+        assert_eq!(remaining(&world), Duration::from_secs(1));
+
+        elapse(&mut world, Duration::from_millis(900));
+        dash(&mut world, dasher);
+        assert_eq!(remaining(&world), DASH_INVULNERABILITY);
     }
 }
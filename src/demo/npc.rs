@@ -0,0 +1,366 @@
+//! Non-player characters, moved by simple behaviors instead of input.
+//!
+//! A [`TiledObject`] of class [`NPC_CLASS`] becomes a character, looking like the player. Its custom
+//! properties pick what it does:
+//!
+//! - `behavior`: `"idle"` (the default), `"wander"` around the object's rectangle, or `"patrol"`
+//!   along a polyline or polygon: the object itself, or the one its `path` property points to.
+//! - `speed`: its top speed, in world units per second.
+//! - `chase_radius`: how close the player has to come for the character to run after them.
+//! - `damage`: how much touching the character hurts.
+//! - `color`: a tint for its sprite.
+//!
+//! Behaviors only write [`MovementController::intent`], so characters move and animate like the
+//! player does.
+
+use std::time::Duration;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use rand::prelude::*;
+use tiled::{ObjectShape, PropertyValue};
+
+use crate::{
+    AppSystems, PausableSystems,
+    asset_tracking::LoadResource,
+    demo::{
+        animation::IDLE,
+        health::{DAMAGE_PROPERTY, Damage},
+        movement::MovementController,
+        player::{PLAYER_COLLIDER_RADIUS, PLAYER_Z_TRANSLATION, Player},
+    },
+    utils::{animation_set::AnimationSet, animator::Animator, tiled::TiledObject},
+};
+
+/// The class of the Tiled objects spawning a character.
+pub const NPC_CLASS: &str = "Npc";
+/// Top speed of characters without a `speed` property, slower than the player.
+pub const NPC_SPEED: f32 = 40.0;
+/// Half the size of the area characters wander in when their object isn't a rectangle.
+pub const WANDER_HALF_SIZE: f32 = 32.0;
+/// Distance to a target under which it counts as reached.
+const ARRIVE_DISTANCE: f32 = 4.0;
+/// Distance to a target under which characters slow down.
+const SLOW_DISTANCE: f32 = 16.0;
+/// A chase only stops once the player is this many times further than the chase radius, so that
+/// it doesn't stop and start at the edge.
+const CHASE_GIVE_UP_FACTOR: f32 = 1.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.load_resource::<NpcAssets>();
+    // Like props, characters can wait for their assets after the map is spawned.
+    app.add_systems(Update, spawn_npcs.run_if(resource_exists::<NpcAssets>));
+    app.add_systems(
+        FixedUpdate,
+        update_npc_intent
+            .in_set(AppSystems::RecordInput)
+            .in_set(PausableSystems),
+    );
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+#[reflect(Component)]
+pub struct Npc;
+
+/// What a character does when it isn't chasing the player.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub enum NpcBehavior {
+    Idle,
+    /// Walks to random points of `region`, pausing in between.
+    Wander {
+        region: Rect,
+        target: Option<Vec2>,
+        /// Times the pause, then the walk to `target`, which is given up on if it's blocked.
+        timer: Timer,
+    },
+    /// Walks from point to point of `path`, back and forth unless `looping`.
+    Patrol {
+        path: Vec<Vec2>,
+        looping: bool,
+        next: usize,
+        forward: bool,
+    },
+}
+
+impl NpcBehavior {
+    pub fn wander(region: Rect) -> Self {
+        Self::Wander {
+            region,
+            target: None,
+            timer: Timer::new(Duration::ZERO, TimerMode::Once),
+        }
+    }
+
+    pub fn patrol(path: Vec<Vec2>, looping: bool) -> Self {
+        Self::Patrol {
+            path,
+            looping,
+            next: 0,
+            forward: true,
+        }
+    }
+
+    /// Where the character at `position` wants to go.
+    fn intent(&mut self, position: Vec2, delta: Duration, rng: &mut impl Rng) -> Vec2 {
+        match self {
+            Self::Idle => Vec2::ZERO,
+            Self::Wander {
+                region,
+                target,
+                timer,
+            } => {
+                timer.tick(delta);
+                match *target {
+                    Some(goal)
+                        if position.distance(goal) > ARRIVE_DISTANCE && !timer.is_finished() =>
+                    {
+                        steer(position, goal)
+                    }
+                    Some(_) => {
+                        *target = None;
+                        timer.set_duration(Duration::from_secs_f32(rng.random_range(1.0..3.0)));
+                        timer.reset();
+                        Vec2::ZERO
+                    }
+                    None if timer.is_finished() => {
+                        let goal = Vec2::new(
+                            rng.random_range(region.min.x..=region.max.x),
+                            rng.random_range(region.min.y..=region.max.y),
+                        );
+                        *target = Some(goal);
+                        timer.set_duration(Duration::from_secs(5));
+                        timer.reset();
+                        steer(position, goal)
+                    }
+                    None => Vec2::ZERO,
+                }
+            }
+            Self::Patrol {
+                path,
+                looping,
+                next,
+                forward,
+            } => {
+                let Some(&goal) = path.get(*next) else {
+                    return Vec2::ZERO;
+                };
+                if position.distance(goal) > ARRIVE_DISTANCE {
+                    return steer(position, goal);
+                }
+                let last = path.len() - 1;
+                if last == 0 {
+                    return Vec2::ZERO;
+                }
+                if *looping {
+                    *next = (*next + 1) % path.len();
+                } else {
+                    // Turn around at the ends.
+                    if *next == last {
+                        *forward = false;
+                    } else if *next == 0 {
+                        *forward = true;
+                    }
+                    *next = if *forward { *next + 1 } else { *next - 1 };
+                }
+                steer(position, path[*next])
+            }
+        }
+    }
+}
+
+/// Makes a character run after the player once they come within `radius`.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct Chase {
+    pub radius: f32,
+    pub chasing: bool,
+}
+
+impl Chase {
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radius,
+            chasing: false,
+        }
+    }
+
+    /// Whether to chase the player that is `distance` away.
+    fn update(&mut self, distance: f32) -> bool {
+        let radius = if self.chasing {
+            self.radius * CHASE_GIVE_UP_FACTOR
+        } else {
+            self.radius
+        };
+        self.chasing = distance <= radius;
+        self.chasing
+    }
+}
+
+/// Full speed towards `target`, slowing down when getting close.
+fn steer(position: Vec2, target: Vec2) -> Vec2 {
+    let offset = target - position;
+    let distance = offset.length();
+    if distance <= ARRIVE_DISTANCE {
+        return Vec2::ZERO;
+    }
+    offset / distance * (distance / SLOW_DISTANCE).min(1.0)
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct NpcAssets {
+    /// Characters are recolored players for now.
+    #[dependency]
+    animations: Handle<AnimationSet>,
+}
+
+impl FromWorld for NpcAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            animations: assets.load("textures/chars/eris_esra-template-20x20.anim.ron"),
+        }
+    }
+}
+
+/// A number property, which Tiled may have stored as an integer.
+fn float_property(object: &TiledObject, name: &str) -> Option<f32> {
+    match object.properties.get(name)? {
+        &PropertyValue::FloatValue(value) => Some(value),
+        &PropertyValue::IntValue(value) => Some(value as f32),
+        _ => None,
+    }
+}
+
+/// The behavior the properties of `object` ask for, with paths looked up in `objects`.
+fn npc_behavior(
+    object: &TiledObject,
+    position: Vec2,
+    objects: &Query<(&TiledObject, &Transform)>,
+) -> NpcBehavior {
+    let behavior = match object.properties.get("behavior") {
+        Some(PropertyValue::StringValue(name)) => name.to_ascii_lowercase(),
+        _ => String::new(),
+    };
+    match behavior.as_str() {
+        "wander" => {
+            let half_size = match object.shape {
+                ObjectShape::Rect { width, height } => Vec2::new(width, height) / 2.0,
+                _ => Vec2::splat(WANDER_HALF_SIZE),
+            };
+            NpcBehavior::wander(Rect::from_center_half_size(position, half_size))
+        }
+        "patrol" => {
+            let path_object = match object.properties.get("path") {
+                Some(&PropertyValue::ObjectValue(id)) => objects
+                    .iter()
+                    .find(|(other, _)| other.map == object.map && other.id == id),
+                _ => None,
+            };
+            let (path_object, origin) = match path_object {
+                Some((path_object, transform)) => (path_object, transform.translation.truncate()),
+                None => (object, position),
+            };
+            let path = path_object
+                .points()
+                .into_iter()
+                .map(|point| origin + point)
+                .collect();
+            let looping = matches!(path_object.shape, ObjectShape::Polygon { .. });
+            NpcBehavior::patrol(path, looping)
+        }
+        "idle" | "" => NpcBehavior::Idle,
+        other => {
+            warn!(
+                "Unknown behavior {other:?} for {:?}, standing still",
+                object.name
+            );
+            NpcBehavior::Idle
+        }
+    }
+}
+
+/// Turns the Tiled objects of class [`NPC_CLASS`] into characters.
+fn spawn_npcs(
+    mut commands: Commands,
+    npc_assets: Res<NpcAssets>,
+    sets: Res<Assets<AnimationSet>>,
+    added_query: Query<(Entity, &TiledObject, &Transform), Added<TiledObject>>,
+    object_query: Query<(&TiledObject, &Transform)>,
+) {
+    for (entity, object, transform) in &added_query {
+        if object.class != NPC_CLASS {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        let behavior = npc_behavior(object, position, &object_query);
+        let max_speed = float_property(object, "speed").unwrap_or(NPC_SPEED);
+
+        let mut sprite = sets
+            .get(&npc_assets.animations)
+            .map(AnimationSet::sprite)
+            .unwrap_or_default();
+        if let Some(PropertyValue::ColorValue(color)) = object.properties.get("color") {
+            sprite.color = Color::srgba_u8(color.red, color.green, color.blue, color.alpha);
+        }
+
+        let mut npc = commands.entity(entity);
+        npc.insert((
+            Npc,
+            behavior,
+            sprite,
+            Animator::new(npc_assets.animations.clone(), IDLE),
+            MovementController {
+                max_speed,
+                acceleration: max_speed * 8.0,
+                deceleration: max_speed * 10.0,
+                turn_rate: 8.0,
+                ..default()
+            },
+            Transform::from_translation(position.extend(PLAYER_Z_TRANSLATION)),
+            RigidBody::Dynamic,
+            TransformInterpolation,
+            LockedAxes::new().lock_rotation(),
+            GravityScale(0.0),
+            Collider::circle(PLAYER_COLLIDER_RADIUS),
+        ));
+        if let Some(radius) = float_property(object, "chase_radius") {
+            npc.insert(Chase::new(radius));
+        }
+        if let Some(&PropertyValue::IntValue(amount)) = object.properties.get(DAMAGE_PROPERTY)
+            && amount > 0
+        {
+            npc.insert(Damage {
+                amount: amount as u32,
+            });
+        }
+    }
+}
+
+fn update_npc_intent(
+    time: Res<Time>,
+    player: Option<Single<&GlobalTransform, With<Player>>>,
+    mut npc_query: Query<
+        (
+            &GlobalTransform,
+            &mut NpcBehavior,
+            Option<&mut Chase>,
+            &mut MovementController,
+        ),
+        With<Npc>,
+    >,
+) {
+    let player_position = player.map(|player| player.translation().truncate());
+    let rng = &mut rand::rng();
+    for (transform, mut behavior, chase, mut controller) in &mut npc_query {
+        let position = transform.translation().truncate();
+        if let (Some(mut chase), Some(player_position)) = (chase, player_position)
+            && chase.update(position.distance(player_position))
+        {
+            controller.intent = steer(position, player_position);
+            continue;
+        }
+        controller.intent = behavior.intent(position, time.delta(), rng);
+    }
+}
@@ -1,7 +1,6 @@
-use bevy::{
-    image::{ImageLoaderSettings, ImageSampler},
-    prelude::*,
-};
+use std::time::Duration;
+
+use bevy::prelude::*;
 
 use avian2d::prelude::*;
 
@@ -9,25 +8,54 @@ use crate::{
     AppSystems, PausableSystems,
     asset_tracking::LoadResource,
     demo::{
-        animation::{Direction, PlayerAnimation},
+        animation::IDLE,
+        health::Health,
         level::PlayerMarker,
-        movement::MovementController,
+        movement::{Dash, MovementController, StartDash},
+    },
+    input::{Action, ActionState, action_just_pressed},
+    utils::{
+        animation_set::AnimationSet,
+        animator::{Animator, AnimatorSystems},
+        cam::CameraTarget,
     },
 };
 
 pub const PLAYER_Z_TRANSLATION: f32 = 100.;
+/// The collider of the player when its animation set has no [`PLAYER_HITBOX_SLICE`].
 pub const PLAYER_COLLIDER_RADIUS: f32 = 10.0;
+/// The slice of the player's animation set its collider is fitted to.
+pub const PLAYER_HITBOX_SLICE: &str = "hitbox";
+pub const PLAYER_MAX_HEALTH: u32 = 5;
+/// Stick tilt below which the stick is considered at rest.
+pub const STICK_DEADZONE: f32 = 0.2;
 
 pub(super) fn plugin(app: &mut App) {
     app.load_resource::<PlayerAssets>();
 
-    // Record directional input as movement controls.
+    // Record directional keyboard and gamepad input as movement controls.
     app.add_systems(
         FixedUpdate,
         record_player_directional_input
             .in_set(AppSystems::RecordInput)
             .in_set(PausableSystems),
     );
+    // A dash is a one-off press, which a fixed timestep could miss.
+    app.add_systems(
+        Update,
+        record_player_dash_input
+            .run_if(action_just_pressed(Action::Dash))
+            .in_set(AppSystems::RecordInput)
+            .in_set(PausableSystems),
+    );
+    // Fit the collider to the frame shown.
+    app.add_systems(
+        Update,
+        fit_player_hitbox
+            .after(AnimatorSystems::Apply)
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
 }
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
@@ -38,103 +66,278 @@ pub struct Player;
 pub fn player(
     max_speed: f32,
     player_assets: &PlayerAssets,
-    texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+    animation_sets: &Assets<AnimationSet>,
 ) -> impl Bundle {
     // A texture atlas is a way to split a single image into a grid of related images.
     // You can learn more in this example: https://github.com/bevyengine/bevy/blob/latest/examples/2d/texture_atlas.rs
-    //let layout = TextureAtlasLayout::from_grid(UVec2::splat(32), 6, 2, UVec2::splat(1)), None);
-    let layout = TextureAtlasLayout::from_grid(UVec2::splat(20), 5, 15, None, None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
-    let player_animation = PlayerAnimation::new();
+    // The grid and the frames of each animation are described in the animation set.
+    let sprite = animation_sets
+        .get(&player_assets.animations)
+        .map(AnimationSet::sprite)
+        .unwrap_or_default();
+    let animator = Animator::new(player_assets.animations.clone(), IDLE);
     (
         Name::new("Player"),
         Player,
-        Sprite::from_atlas_image(
-            player_assets.char.clone(),
-            TextureAtlas {
-                layout: texture_atlas_layout,
-                index: player_animation.get_atlas_index(),
-            },
-        ),
+        sprite,
         MovementController {
             max_speed,
+            acceleration: max_speed * 8.0,
+            deceleration: max_speed * 10.0,
+            turn_rate: 12.0,
             ..default()
         },
-        player_animation,
+        Dash::new(
+            max_speed * 3.0,
+            Duration::from_millis(150),
+            Duration::from_millis(600),
+            Duration::from_millis(250),
+        ),
+        animator,
+        Health::new(PLAYER_MAX_HEALTH),
         PlayerMarker,
+        CameraTarget::default(),
         LockedAxes::new().lock_rotation(),
         Transform::from_xyz(0., 0., PLAYER_Z_TRANSLATION),
         RigidBody::Dynamic,
+        // Physics runs on a fixed timestep, so smooth out the rendering in between.
+        TransformInterpolation,
         GravityScale(0.0),
         Collider::circle(PLAYER_COLLIDER_RADIUS),
+        // What the player touches, to be hurt by it.
+        CollidingEntities::default(),
     )
 }
 
 fn record_player_directional_input(
-    input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
+    gamepads: Query<&Gamepad>,
+    mut axes: Local<[DigitalAxis; 2]>,
     mut controller_query: Query<&mut MovementController, With<Player>>,
 ) {
     // Collect directional input.
-    let mut _intent = Vec2::ZERO;
+    let [x_axis, y_axis] = &mut *axes;
+    let digital = Vec2::new(
+        x_axis.update(
+            actions.pressed(Action::MoveLeft),
+            actions.pressed(Action::MoveRight),
+        ),
+        y_axis.update(
+            actions.pressed(Action::MoveDown),
+            actions.pressed(Action::MoveUp),
+        ),
+    );
 
-    if input.pressed(KeyCode::KeyW) {
-        _intent.y += 1.0;
-    }
-    if input.pressed(KeyCode::KeyS) {
-        _intent.y -= 1.0;
+    // The sticks are not bound to actions: the most tilted one wins over the buttons.
+    let mut intent = digital.normalize_or_zero();
+    for gamepad in &gamepads {
+        let stick = apply_deadzone(gamepad.left_stick(), STICK_DEADZONE);
+        if stick.length_squared() > intent.length_squared() {
+            intent = stick;
+        }
     }
-    if input.pressed(KeyCode::KeyA) {
-        _intent.x -= 1.0;
+
+    for mut controller in &mut controller_query {
+        controller.intent = intent;
     }
-    if input.pressed(KeyCode::KeyD) {
-        _intent.x += 1.0;
+}
+
+fn record_player_dash_input(mut commands: Commands, player_query: Query<Entity, With<Player>>) {
+    for entity in &player_query {
+        commands.trigger(StartDash { entity });
     }
+}
 
-    use Direction::*;
-    let dir = match _intent {
-        Vec2 { x: 0.0, y: 0.0 } => Nothing,
-        Vec2 { x: 0.0, y: 1.0 } => Up,
-        Vec2 { x: 0.0, y: -1.0 } => Down,
-        Vec2 { x: 1.0, y: 0.0 } => Right,
-        Vec2 { x: -1.0, y: 0.0 } => Left,
-        Vec2 { x: 1.0, y: 1.0 } => UpRight,
-        Vec2 { x: -1.0, y: 1.0 } => UpLeft,
-        Vec2 { x: 1.0, y: -1.0 } => DownRight,
-        Vec2 { x: -1.0, y: -1.0 } => DownLeft,
-        _ => panic!("Unknown intent"),
+/// Fits the collider of the player to the [`PLAYER_HITBOX_SLICE`] of the frame it shows, flipped
+/// along with the sprite.
+fn fit_player_hitbox(
+    player_assets: If<Res<PlayerAssets>>,
+    animation_sets: Res<Assets<AnimationSet>>,
+    mut player_query: Query<(Entity, &Sprite, &mut Collider), (With<Player>, Changed<Sprite>)>,
+    mut fitted: Local<Option<(Entity, Rect)>>,
+) {
+    let Some(set) = animation_sets.get(&player_assets.animations) else {
+        return;
     };
+    for (entity, sprite, mut collider) in &mut player_query {
+        let Some(atlas) = &sprite.texture_atlas else {
+            continue;
+        };
+        let Some(hitbox) = set.slice(PLAYER_HITBOX_SLICE, atlas.index, sprite.flip_x) else {
+            continue;
+        };
+        // Replacing the collider makes the physics recompute it, so only do it on changes.
+        if *fitted == Some((entity, hitbox)) {
+            continue;
+        }
+        *fitted = Some((entity, hitbox));
+        let size = hitbox.size();
+        *collider = Collider::compound(vec![(
+            Position::new(hitbox.center()),
+            Rotation::IDENTITY,
+            Collider::rectangle(size.x, size.y),
+        )]);
+    }
+}
 
-    for mut controller in &mut controller_query {
-        controller.intent = dir.clone();
+/// One axis of digital input. When both of its directions are held, the last one pressed wins.
+#[derive(Debug, Default)]
+struct DigitalAxis {
+    negative: bool,
+    positive: bool,
+    last_pressed: f32,
+}
+
+impl DigitalAxis {
+    /// Updates the held directions and returns -1, 0 or 1.
+    fn update(&mut self, negative: bool, positive: bool) -> f32 {
+        if negative && !self.negative {
+            self.last_pressed = -1.0;
+        }
+        if positive && !self.positive {
+            self.last_pressed = 1.0;
+        }
+        self.negative = negative;
+        self.positive = positive;
+        match (negative, positive) {
+            (true, true) => self.last_pressed,
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            (false, false) => 0.0,
+        }
+    }
+}
+
+/// Ignores tilts shorter than `deadzone` and rescales the rest, so that movement starts from 0
+/// at the edge of the deadzone and reaches 1 at full tilt.
+fn apply_deadzone(stick: Vec2, deadzone: f32) -> Vec2 {
+    let tilt = stick.length();
+    if tilt <= deadzone {
+        return Vec2::ZERO;
     }
+    stick / tilt * ((tilt - deadzone) / (1.0 - deadzone)).min(1.0)
 }
 
 #[derive(Resource, Asset, Clone, Reflect)]
 #[reflect(Resource)]
 pub struct PlayerAssets {
     #[dependency]
-    char: Handle<Image>,
-    #[dependency]
-    pub steps: Vec<Handle<AudioSource>>,
+    animations: Handle<AnimationSet>,
 }
 
 impl FromWorld for PlayerAssets {
     fn from_world(world: &mut World) -> Self {
         let assets = world.resource::<AssetServer>();
         Self {
-            char: assets.load_with_settings(
-                "textures/chars/eris_esra-template-20x20.png",
-                |settings: &mut ImageLoaderSettings| {
-                    // Use `nearest` image sampling to preserve pixel art style.
-                    settings.sampler = ImageSampler::nearest();
-                },
-            ),
-            steps: vec![
-                assets.load("audio/sound_effects/step1.ogg"),
-                assets.load("audio/sound_effects/step2.ogg"),
-                assets.load("audio/sound_effects/step3.ogg"),
-                assets.load("audio/sound_effects/step4.ogg"),
-            ],
+            // The sheet uses the default `nearest` sampling, which preserves the pixel art style.
+            animations: assets.load("textures/chars/eris_esra-template-20x20.anim.ron"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds `(negative, positive, expected)` frames to a fresh axis.
+    fn check_axis(frames: &[(bool, bool, f32)]) {
+        let mut axis = DigitalAxis::default();
+        for (frame, &(negative, positive, expected)) in frames.iter().enumerate() {
+            assert_eq!(
+                axis.update(negative, positive),
+                expected,
+                "frame {frame} of {frames:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn single_direction_follows_the_button() {
+        check_axis(&[
+            (false, false, 0.0),
+            (true, false, -1.0),
+            (false, false, 0.0),
+        ]);
+        check_axis(&[(false, false, 0.0), (false, true, 1.0), (false, false, 0.0)]);
+    }
+
+    #[test]
+    fn last_pressed_wins() {
+        // Negative first, then positive, released in either order.
+        check_axis(&[
+            (true, false, -1.0),
+            (true, true, 1.0),
+            (true, false, -1.0),
+            (false, false, 0.0),
+        ]);
+        check_axis(&[
+            (true, false, -1.0),
+            (true, true, 1.0),
+            (false, true, 1.0),
+            (false, false, 0.0),
+        ]);
+        // Positive first, then negative, released in either order.
+        check_axis(&[
+            (false, true, 1.0),
+            (true, true, -1.0),
+            (false, true, 1.0),
+            (false, false, 0.0),
+        ]);
+        check_axis(&[
+            (false, true, 1.0),
+            (true, true, -1.0),
+            (true, false, -1.0),
+            (false, false, 0.0),
+        ]);
+        // Pressing again while the other one is held takes over again.
+        check_axis(&[
+            (true, false, -1.0),
+            (true, true, 1.0),
+            (false, true, 1.0),
+            (true, true, -1.0),
+        ]);
+    }
+
+    #[test]
+    fn simultaneous_presses_go_positive() {
+        check_axis(&[(true, true, 1.0), (true, false, -1.0), (false, false, 0.0)]);
+        // Also once the negative one has been held and released.
+        check_axis(&[(true, false, -1.0), (false, false, 0.0), (true, true, 1.0)]);
+    }
+
+    #[test]
+    fn deadzone_ignores_small_tilts() {
+        assert_eq!(apply_deadzone(Vec2::ZERO, STICK_DEADZONE), Vec2::ZERO);
+        assert_eq!(
+            apply_deadzone(Vec2::new(0.1, -0.1), STICK_DEADZONE),
+            Vec2::ZERO
+        );
+        assert_eq!(
+            apply_deadzone(Vec2::new(0.0, STICK_DEADZONE), STICK_DEADZONE),
+            Vec2::ZERO
+        );
+    }
+
+    #[test]
+    fn deadzone_rescales_from_its_edge_to_full_tilt() {
+        let just_out = apply_deadzone(Vec2::new(STICK_DEADZONE + 1e-4, 0.0), STICK_DEADZONE);
+        assert!(just_out.x > 0.0 && just_out.x < 1e-3, "{just_out}");
+
+        let halfway = (1.0 + STICK_DEADZONE) / 2.0;
+        let half = apply_deadzone(Vec2::new(-halfway, 0.0), STICK_DEADZONE);
+        assert!(half.abs_diff_eq(Vec2::new(-0.5, 0.0), 1e-5), "{half}");
+
+        let full = apply_deadzone(Vec2::new(0.0, 1.0), STICK_DEADZONE);
+        assert!(full.abs_diff_eq(Vec2::Y, 1e-5), "{full}");
+    }
+
+    #[test]
+    fn deadzone_caps_at_full_tilt_and_keeps_the_direction() {
+        // Square gates report diagonals longer than 1.
+        let diagonal = apply_deadzone(Vec2::ONE, STICK_DEADZONE);
+        assert!(
+            diagonal.abs_diff_eq(Vec2::ONE.normalize(), 1e-5),
+            "{diagonal}"
+        );
+    }
+}
@@ -0,0 +1,307 @@
+//! Animated props placed from Tiled: torches, vases, pillars...
+//!
+//! A [`TiledObject`] whose class names a prop of a `.props.ron` file becomes that prop. Each prop names
+//! its sheet, how it is cut, the frames it loops through and, optionally, its collider, the
+//! frames of its other animation states and the frames played once between two states:
+//!
+//! ```ron
+//! (
+//!     props: {
+//!         "Vase": (
+//!             image: "DungeonGathering_Vase-Shine-Anim.png",
+//!             grid: (tile_size: (16, 16), columns: 16, rows: 1),
+//!             frames: [0, 1, 2, 3],
+//!             fps: 8.0,
+//!             // In pixels, from the center of the sprite.
+//!             collider: Some((shape: Rectangle(width: 12.0, height: 10.0), offset: (0.0, -3.0))),
+//!             // Played with `Animator::set_state`.
+//!             states: {"broken": [4]},
+//!             transitions: [(from: "idle", to: "broken", frames: [5, 6])],
+//!         ),
+//!     },
+//! )
+//! ```
+
+use avian2d::prelude::*;
+use bevy::{
+    asset::{AssetLoader, LoadContext, ParseAssetPathError, io::Reader},
+    platform::collections::HashMap,
+    prelude::*,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    asset_tracking::LoadResource,
+    demo::{animation::IDLE, player::PLAYER_Z_TRANSLATION},
+    utils::{
+        animation_set::{AnimationClip, AnimationGrid, AnimationSet, Direction},
+        animator::{AnimationTransition, Animator},
+        tiled::TiledObject,
+    },
+};
+
+/// Props are drawn under the player.
+pub const PROP_Z_TRANSLATION: f32 = PLAYER_Z_TRANSLATION - 1.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<PropCatalog>();
+    app.register_asset_loader(PropCatalogLoader);
+    app.load_resource::<PropAssets>();
+    // Maps can spawn their objects before the catalog is loaded. Objects added meanwhile count as
+    // added on the first run.
+    app.add_systems(Update, spawn_props.run_if(resource_exists::<PropAssets>));
+}
+
+/// A prop spawned from the catalog, by class name.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Prop {
+    pub class: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum PropShape {
+    Rectangle { width: f32, height: f32 },
+    Circle { radius: f32 },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PropCollider {
+    pub shape: PropShape,
+    /// From the center of the sprite, in pixels.
+    #[serde(default)]
+    pub offset: Vec2,
+    /// Whether it detects overlaps without blocking, e.g. for pickups.
+    #[serde(default)]
+    pub sensor: bool,
+}
+
+impl PropCollider {
+    fn collider(&self) -> Collider {
+        match self.shape {
+            PropShape::Rectangle { width, height } => Collider::rectangle(width, height),
+            PropShape::Circle { radius } => Collider::circle(radius),
+        }
+    }
+}
+
+/// A kind of prop, once loaded.
+#[derive(Debug, Clone)]
+pub struct PropKind {
+    /// A clip named [`IDLE`], and one per other state.
+    pub animations: Handle<AnimationSet>,
+    pub collider: Option<PropCollider>,
+    /// Clips of the same set played between two states.
+    pub transitions: Vec<AnimationTransition>,
+}
+
+/// The props of a `.props.ron` file, by class name.
+#[derive(Asset, TypePath, Debug)]
+pub struct PropCatalog {
+    pub props: HashMap<String, PropKind>,
+    /// The same animations, so that the catalog is only ready along with their sheets.
+    #[dependency]
+    animations: Vec<Handle<AnimationSet>>,
+}
+
+/// A prop as written in a `.props.ron` file.
+#[derive(Debug, Deserialize)]
+struct PropFile {
+    /// Path of the sheet, relative to the file.
+    image: String,
+    grid: AnimationGrid,
+    frames: Vec<usize>,
+    /// Frames per second.
+    #[serde(default = "fps_default")]
+    fps: f32,
+    #[serde(default)]
+    collider: Option<PropCollider>,
+    /// The frames of other animation states, played at the same rate.
+    #[serde(default)]
+    states: HashMap<String, Vec<usize>>,
+    /// Frames played once when the state changes, at the same rate.
+    #[serde(default)]
+    transitions: Vec<PropTransition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PropTransition {
+    from: String,
+    to: String,
+    frames: Vec<usize>,
+}
+
+fn fps_default() -> f32 {
+    8.0
+}
+
+#[derive(Debug, Deserialize)]
+struct PropCatalogFile {
+    props: HashMap<String, PropFile>,
+}
+
+#[derive(Debug, Error)]
+pub enum PropCatalogLoaderError {
+    #[error("Could not load prop catalog: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse prop catalog: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("Invalid image path in prop catalog: {0}")]
+    ImagePath(#[from] ParseAssetPathError),
+}
+
+#[derive(TypePath)]
+pub struct PropCatalogLoader;
+
+impl AssetLoader for PropCatalogLoader {
+    type Asset = PropCatalog;
+    type Settings = ();
+    type Error = PropCatalogLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file: PropCatalogFile = ron::de::from_bytes(&bytes)?;
+
+        let mut props = HashMap::default();
+        for (class, prop) in file.props {
+            let image_path = load_context.path().resolve_embed(&prop.image)?;
+            let image = load_context.load(image_path);
+            let layout = load_context.add_labeled_asset(
+                format!("{class}/layout"),
+                TextureAtlasLayout::from_grid(
+                    prop.grid.tile_size,
+                    prop.grid.columns,
+                    prop.grid.rows,
+                    prop.grid.padding,
+                    prop.grid.offset,
+                ),
+            );
+            let clip = |frames, looping| -> HashMap<Direction, AnimationClip> {
+                let clip = AnimationClip {
+                    frames,
+                    frame_duration: 1.0 / prop.fps.max(f32::EPSILON),
+                    frame_durations: Vec::new(),
+                    looping,
+                    events: default(),
+                };
+                [(Direction::Nothing, clip)].into_iter().collect()
+            };
+            let mut clips: HashMap<_, _> = prop
+                .states
+                .into_iter()
+                .chain([(IDLE.to_string(), prop.frames)])
+                .map(|(state, frames)| (state, clip(frames, true)))
+                .collect();
+            let transitions = prop
+                .transitions
+                .into_iter()
+                .map(|transition| {
+                    let via = format!("{} to {}", transition.from, transition.to);
+                    clips.insert(via.clone(), clip(transition.frames, false));
+                    AnimationTransition {
+                        from: transition.from,
+                        to: transition.to,
+                        via,
+                    }
+                })
+                .collect();
+            let animations = load_context.add_labeled_asset(
+                class.clone(),
+                AnimationSet {
+                    image,
+                    layout,
+                    frame_size: prop.grid.tile_size,
+                    clips,
+                    mirror_x: default(),
+                    slices: default(),
+                },
+            );
+            props.insert(
+                class,
+                PropKind {
+                    animations,
+                    collider: prop.collider,
+                    transitions,
+                },
+            );
+        }
+
+        Ok(PropCatalog {
+            animations: props.values().map(|prop| prop.animations.clone()).collect(),
+            props,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["props.ron"]
+    }
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct PropAssets {
+    #[dependency]
+    catalog: Handle<PropCatalog>,
+}
+
+impl FromWorld for PropAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            catalog: assets.load("textures/props/dungeon.props.ron"),
+        }
+    }
+}
+
+/// Turns the Tiled objects whose class is in the catalog into props.
+fn spawn_props(
+    mut commands: Commands,
+    prop_assets: Res<PropAssets>,
+    catalogs: Res<Assets<PropCatalog>>,
+    sets: Res<Assets<AnimationSet>>,
+    mut object_query: Query<(Entity, &TiledObject, &mut Transform), Added<TiledObject>>,
+) {
+    let Some(catalog) = catalogs.get(&prop_assets.catalog) else {
+        return;
+    };
+    for (entity, object, mut transform) in &mut object_query {
+        let Some(kind) = catalog.props.get(&object.class) else {
+            continue;
+        };
+        let Some(set) = sets.get(&kind.animations) else {
+            continue;
+        };
+        transform.translation.z = PROP_Z_TRANSLATION;
+        commands.entity(entity).insert((
+            Prop {
+                class: object.class.clone(),
+            },
+            set.sprite(),
+            kind.transitions.iter().fold(
+                Animator::new(kind.animations.clone(), IDLE),
+                |animator, transition| {
+                    animator.with_transition(&transition.from, &transition.to, &transition.via)
+                },
+            ),
+        ));
+        if let Some(collider) = &kind.collider {
+            commands.entity(entity).insert(RigidBody::Static);
+            let mut collider_entity = commands.spawn((
+                Name::new("Prop Collider"),
+                Transform::from_translation(collider.offset.extend(0.0)),
+                collider.collider(),
+                ChildOf(entity),
+            ));
+            if collider.sensor {
+                collider_entity.insert(Sensor);
+            }
+        }
+    }
+}
@@ -0,0 +1,363 @@
+//! Floor switches, and the doors they open.
+//!
+//! A [`SWITCH_CLASS`] prop is a [`Switch`]: the player interacting with it presses or releases it,
+//! and sends [`Toggle`] to the Tiled objects that its object properties point to. A [`DOOR_CLASS`]
+//! object is a [`Door`]: opening it removes the tiles under its rectangle, from the layer named by
+//! its [`DOOR_LAYER_PROPERTY`] or else the topmost one, and closing it puts them back.
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+use tiled::{ObjectShape, PropertyValue};
+
+use crate::{
+    asset_tracking::LoadResource,
+    audio::sound_effect,
+    demo::{
+        animation::IDLE,
+        interaction::{Interact, Interactable},
+        player::Player,
+        props::Prop,
+    },
+    utils::{
+        animator::Animator,
+        tiled::{
+            TiledObject, TiledTile,
+            edit::{RemoveTile, SetTile},
+            query::TiledMapQuery,
+        },
+    },
+};
+
+/// The class of the props that are switches.
+pub const SWITCH_CLASS: &str = "Button";
+/// The class of the Tiled objects that are doors.
+pub const DOOR_CLASS: &str = "Door";
+/// The custom property of doors naming the layer of their tiles.
+pub const DOOR_LAYER_PROPERTY: &str = "layer";
+/// The animation state of a pressed switch.
+pub const PRESSED: &str = "pressed";
+/// How close the player has to be to press a switch.
+pub const SWITCH_RANGE: f32 = 20.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.load_resource::<SwitchAssets>();
+    app.add_observer(make_prop_switch);
+    app.add_observer(make_object_door);
+    app.add_observer(press_switch);
+    app.add_observer(toggle_door);
+}
+
+/// Sent to the objects linked to a [`Switch`] when it is pressed or released.
+#[derive(EntityEvent, Debug, Clone, Copy)]
+pub struct Toggle {
+    pub entity: Entity,
+}
+
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Switch {
+    pub pressed: bool,
+    /// Ids of the Tiled objects it toggles, in the same map.
+    pub targets: Vec<u32>,
+}
+
+/// Tiles that go away when opened.
+#[derive(Component, Debug, Clone, Default)]
+pub struct Door {
+    pub open: bool,
+    /// The removed tiles while open.
+    tiles: Vec<DoorTile>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DoorTile {
+    layer_index: u32,
+    tile_pos: TilePos,
+    tile: TiledTile,
+    flip: TileFlip,
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct SwitchAssets {
+    /// There is no dedicated switch sound yet.
+    #[dependency]
+    click: Handle<AudioSource>,
+}
+
+impl FromWorld for SwitchAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            click: assets.load("audio/sound_effects/button_click.ogg"),
+        }
+    }
+}
+
+fn make_prop_switch(
+    add: On<Add, Prop>,
+    mut commands: Commands,
+    prop_query: Query<(&Prop, Option<&TiledObject>)>,
+) {
+    let Ok((prop, object)) = prop_query.get(add.entity) else {
+        return;
+    };
+    if prop.class != SWITCH_CLASS {
+        return;
+    }
+    // Unset object properties are saved as 0.
+    let mut targets: Vec<u32> = object
+        .into_iter()
+        .flat_map(|object| object.properties.values())
+        .filter_map(|value| match *value {
+            PropertyValue::ObjectValue(id) if id != 0 => Some(id),
+            _ => None,
+        })
+        .collect();
+    targets.sort_unstable();
+    commands.entity(add.entity).insert((
+        Switch {
+            pressed: false,
+            targets,
+        },
+        Interactable::new(SWITCH_RANGE, "Use"),
+    ));
+}
+
+fn make_object_door(
+    add: On<Add, TiledObject>,
+    mut commands: Commands,
+    object_query: Query<&TiledObject>,
+) {
+    if object_query
+        .get(add.entity)
+        .is_ok_and(|object| object.class == DOOR_CLASS)
+    {
+        commands.entity(add.entity).insert(Door::default());
+    }
+}
+
+fn press_switch(
+    interact: On<Interact>,
+    mut commands: Commands,
+    switch_assets: If<Res<SwitchAssets>>,
+    mut switch_query: Query<(&mut Switch, &TiledObject, Option<&mut Animator>)>,
+    object_query: Query<(Entity, &TiledObject)>,
+    player_query: Query<(), With<Player>>,
+) {
+    // Other characters, e.g. NPCs, leave switches alone.
+    if !player_query.contains(interact.interactor) {
+        return;
+    }
+    let Ok((mut switch, object, animator)) = switch_query.get_mut(interact.entity) else {
+        return;
+    };
+    switch.pressed = !switch.pressed;
+    if let Some(mut animator) = animator {
+        animator.set_state(if switch.pressed { PRESSED } else { IDLE });
+    }
+    for (target, _) in object_query
+        .iter()
+        .filter(|(_, other)| other.map == object.map && switch.targets.contains(&other.id))
+    {
+        commands.trigger(Toggle { entity: target });
+    }
+    commands.spawn(sound_effect(switch_assets.click.clone()));
+}
+
+fn toggle_door(
+    toggle: On<Toggle>,
+    mut commands: Commands,
+    tiled_query: TiledMapQuery,
+    flip_query: Query<&TileFlip>,
+    mut door_query: Query<(&mut Door, &TiledObject, &GlobalTransform)>,
+) {
+    let Ok((mut door, object, transform)) = door_query.get_mut(toggle.entity) else {
+        return;
+    };
+    if door.open {
+        for tile in door.tiles.drain(..) {
+            commands.queue(SetTile {
+                map: object.map,
+                layer_index: tile.layer_index,
+                tile_pos: tile.tile_pos,
+                tile: tile.tile,
+                flip: tile.flip,
+            });
+        }
+    } else {
+        door.tiles = door_tiles(
+            &tiled_query,
+            &flip_query,
+            object,
+            transform.translation().truncate(),
+        );
+        for tile in &door.tiles {
+            commands.queue(RemoveTile {
+                map: object.map,
+                layer_index: tile.layer_index,
+                tile_pos: tile.tile_pos,
+            });
+        }
+    }
+    door.open = !door.open;
+}
+
+/// The tiles under the rectangle of a door centered on `center`, or under its tile for other
+/// shapes.
+fn door_tiles(
+    tiled_query: &TiledMapQuery,
+    flip_query: &Query<&TileFlip>,
+    object: &TiledObject,
+    center: Vec2,
+) -> Vec<DoorTile> {
+    let Some(tiled_map) = tiled_query.map(object.map) else {
+        return Vec::new();
+    };
+    let tile_size = Vec2::new(
+        tiled_map.map.tile_width as f32,
+        tiled_map.map.tile_height as f32,
+    );
+    let size = match object.shape {
+        ObjectShape::Rect { width, height } => Vec2::new(width, height),
+        _ => tile_size,
+    };
+    let layer_name = match object.properties.get(DOOR_LAYER_PROPERTY) {
+        Some(PropertyValue::StringValue(name)) => Some(name.as_str()),
+        Some(value) => {
+            warn!(
+                "Door {} has a `{DOOR_LAYER_PROPERTY}` property that is not a layer name: {value:?}",
+                object.id
+            );
+            return Vec::new();
+        }
+        None => None,
+    };
+    if let Some(name) = layer_name
+        && !tiled_map.map.layers().any(|layer| layer.name == name)
+    {
+        warn!(
+            "Door {} opens layer {name:?}, which the map doesn't have",
+            object.id
+        );
+        return Vec::new();
+    }
+
+    let counts = (size / tile_size).round().max(Vec2::ONE).as_uvec2();
+    let first = center - size / 2.0 + tile_size / 2.0;
+    let mut tiles = Vec::new();
+    for row in 0..counts.y {
+        for column in 0..counts.x {
+            let position = first + Vec2::new(column as f32, row as f32) * tile_size;
+            let Some(info) = tiled_query
+                .tiles_at(object.map, position)
+                .into_iter()
+                .find(|info| layer_name.is_none_or(|name| info.layer.name == name))
+            else {
+                continue;
+            };
+            tiles.push(DoorTile {
+                layer_index: info.layer_index,
+                tile_pos: info.tile_pos,
+                tile: TiledTile {
+                    tileset_index: info.tileset_index,
+                    id: info.tile_id,
+                },
+                flip: flip_query
+                    .get(info.tile_entity)
+                    .copied()
+                    .unwrap_or_default(),
+            });
+        }
+    }
+    tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tiled::{
+        TiledLayersStorage,
+        testing::{SMALL_MAP, app, load_map, spawn_map},
+    };
+
+    /// The door covers the wall in the top-left corner of [`SMALL_MAP`], and the switch is next
+    /// to it.
+    const DOOR_OBJECTS: &str = r#" <objectgroup id="3" name="Objects">
+  <object id="1" name="Switch" x="16" y="0" width="16" height="16">
+   <properties>
+    <property name="door" type="object" value="2"/>
+   </properties>
+  </object>
+  <object id="2" name="Door" type="Door" x="0" y="0" width="16" height="16">
+   <properties>
+    <property name="layer" value="Walls"/>
+   </properties>
+  </object>
+ </objectgroup>
+</map>"#;
+
+    fn object(world: &mut World, id: u32) -> Entity {
+        world
+            .query::<(Entity, &TiledObject)>()
+            .iter(world)
+            .find_map(|(entity, object)| (object.id == id).then_some(entity))
+            .unwrap()
+    }
+
+    /// The tile ids of the walls layer.
+    fn walls(world: &World, map: Entity) -> Vec<Option<tiled::TileId>> {
+        let layer = world.get::<TiledLayersStorage>(map).unwrap().by_name["Walls"];
+        world
+            .get::<TileStorage>(layer)
+            .unwrap()
+            .iter()
+            .map(|tile| tile.map(|tile| world.get::<TiledTile>(tile).unwrap().id))
+            .collect()
+    }
+
+    #[test]
+    fn switches_open_and_close_their_doors() {
+        let mut app = app();
+        app.add_plugins(TransformPlugin)
+            .insert_resource(SwitchAssets { click: default() })
+            .add_observer(make_prop_switch)
+            .add_observer(make_object_door)
+            .add_observer(press_switch)
+            .add_observer(toggle_door);
+        let bytes = SMALL_MAP.replace("</map>", DOOR_OBJECTS);
+        let map = spawn_map(&mut app, load_map(bytes.as_bytes(), "small.tmx"));
+        // Place the objects.
+        app.update();
+
+        let world = app.world_mut();
+        let switch = object(world, 1);
+        world.entity_mut(switch).insert(Prop {
+            class: SWITCH_CLASS.to_string(),
+        });
+        let player = world.spawn(Player).id();
+        // Bottom row first.
+        let closed = walls(world, map);
+        assert_eq!(closed, [None, None, Some(1), Some(1), None, None]);
+
+        let interact = |world: &mut World, interactor| {
+            world.trigger(Interact {
+                entity: switch,
+                interactor,
+            });
+        };
+        interact(world, player);
+        assert!(world.get::<Switch>(switch).unwrap().pressed);
+        let open = walls(world, map);
+        assert_eq!(open, [None, None, Some(1), None, None, None]);
+
+        // Only the player uses switches.
+        let npc = world.spawn_empty().id();
+        interact(world, npc);
+        assert_eq!(walls(world, map), open);
+
+        interact(world, player);
+        assert!(!world.get::<Switch>(switch).unwrap().pressed);
+        assert_eq!(walls(world, map), closed);
+    }
+}
@@ -1,10 +1,17 @@
 //! Development tools for the game. This plugin is only enabled in dev builds.
 
-use bevy::{
-    dev_tools::states::log_transitions, input::common_conditions::input_just_pressed, prelude::*,
-};
+use bevy::{dev_tools::states::log_transitions, prelude::*};
 
-use crate::screens::Screen;
+#[cfg(not(target_family = "wasm"))]
+use crate::utils::tiled::{TiledMap, TiledMapHandle, writer::SaveMap};
+use crate::{
+    input::{Action, action_just_pressed},
+    screens::Screen,
+    utils::{
+        cam::{CameraZoom, FocusCamera, ReleaseCameraFocus, ZoomCamera},
+        tiled::TiledMapInfo,
+    },
+};
 
 pub(super) fn plugin(app: &mut App) {
     // Log `Screen` state transitions.
@@ -13,12 +20,66 @@ pub(super) fn plugin(app: &mut App) {
     // Toggle the debug overlay for UI.
     app.add_systems(
         Update,
-        toggle_debug_ui.run_if(input_just_pressed(TOGGLE_KEY)),
+        toggle_debug_ui.run_if(action_just_pressed(Action::ToggleDebug)),
     );
-}
 
-const TOGGLE_KEY: KeyCode = KeyCode::Backquote;
+    // Look at the whole map, e.g. to check a layout.
+    app.add_systems(
+        Update,
+        toggle_map_overview.run_if(action_just_pressed(Action::MapOverview)),
+    );
+
+    // Save the spawned maps along with their runtime edits.
+    #[cfg(not(target_family = "wasm"))]
+    app.add_systems(
+        Update,
+        save_maps.run_if(action_just_pressed(Action::SaveMaps)),
+    );
+}
 
 fn toggle_debug_ui(mut options: ResMut<UiDebugOptions>) {
     options.toggle();
 }
+
+/// Points the camera at the center of the map from as far as it zooms out, and gives it back to
+/// the player with the previous zoom when pressed again.
+fn toggle_map_overview(
+    mut commands: Commands,
+    mut previous_zoom: Local<Option<f32>>,
+    map_query: Query<&TiledMapInfo>,
+    camera_query: Query<&CameraZoom>,
+) {
+    if let Some(scale) = previous_zoom.take() {
+        commands.trigger(ReleaseCameraFocus);
+        commands.trigger(ZoomCamera { scale });
+        return;
+    }
+    let (Some(map_info), Some(zoom)) = (map_query.iter().next(), camera_query.iter().next()) else {
+        return;
+    };
+    *previous_zoom = Some(zoom.target);
+    commands.trigger(FocusCamera {
+        point: map_info.bounds.center(),
+        cut: false,
+    });
+    // Clamped to the furthest zoom.
+    commands.trigger(ZoomCamera { scale: f32::MAX });
+}
+
+/// Saves every map next to its original as `<name>.edited.tmx`, so that the relative tileset
+/// paths still resolve.
+#[cfg(not(target_family = "wasm"))]
+fn save_maps(
+    mut commands: Commands,
+    maps: Res<Assets<TiledMap>>,
+    map_query: Query<(Entity, &TiledMapHandle)>,
+) {
+    for (map, handle) in &map_query {
+        let Some(tiled_map) = maps.get(&handle.0) else {
+            continue;
+        };
+        let path =
+            std::path::Path::new("assets").join(tiled_map.map.source.with_extension("edited.tmx"));
+        commands.queue(SaveMap { map, path });
+    }
+}
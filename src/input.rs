@@ -0,0 +1,294 @@
+//! Map keyboard, mouse and gamepad buttons to game actions, so that systems ask whether
+//! [`Action::Pause`] was pressed rather than [`KeyCode::Escape`], and players can rebind them.
+//!
+//! The bindings are read from [`BINDINGS_PATH`] on native builds, and written back whenever they
+//! are edited.
+
+use std::{collections::BTreeMap, fmt};
+
+use bevy::{input::InputSystems, platform::collections::HashSet, prelude::*};
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_family = "wasm"))]
+use crate::config;
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(InputBindings::load());
+    app.init_resource::<ActionState>();
+    app.add_systems(PreUpdate, update_action_state.after(InputSystems));
+    #[cfg(not(target_family = "wasm"))]
+    app.add_systems(
+        Update,
+        // Not when first inserted, which would rewrite the file on every launch.
+        save_bindings
+            .run_if(resource_changed::<InputBindings>.and(not(resource_added::<InputBindings>))),
+    );
+}
+
+/// Where the bindings are stored, relative to the working directory.
+pub const BINDINGS_PATH: &str = "config/input.ron";
+
+/// Something the player can do, independently of how it is bound.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect, Serialize, Deserialize,
+)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Dash,
+    Interact,
+    ZoomIn,
+    ZoomOut,
+    /// Pause and resume the game.
+    Pause,
+    /// Leave the current menu or screen.
+    Back,
+    ToggleDebug,
+    SaveMaps,
+    /// Look at the whole map, see `dev_tools`.
+    MapOverview,
+}
+
+impl Action {
+    pub const ALL: [Action; 13] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Dash,
+        Action::Interact,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::Pause,
+        Action::Back,
+        Action::ToggleDebug,
+        Action::SaveMaps,
+        Action::MapOverview,
+    ];
+
+    /// The bindings an action has until the player changes them.
+    pub fn default_bindings(self) -> Vec<Binding> {
+        use Binding::*;
+        match self {
+            Action::MoveUp => vec![Key(KeyCode::KeyW), Gamepad(GamepadButton::DPadUp)],
+            Action::MoveDown => vec![Key(KeyCode::KeyS), Gamepad(GamepadButton::DPadDown)],
+            Action::MoveLeft => vec![Key(KeyCode::KeyA), Gamepad(GamepadButton::DPadLeft)],
+            Action::MoveRight => vec![Key(KeyCode::KeyD), Gamepad(GamepadButton::DPadRight)],
+            Action::Dash => vec![Key(KeyCode::Space), Gamepad(GamepadButton::West)],
+            Action::Interact => vec![Key(KeyCode::KeyE), Gamepad(GamepadButton::South)],
+            Action::ZoomIn => vec![Key(KeyCode::Equal)],
+            Action::ZoomOut => vec![Key(KeyCode::Minus)],
+            Action::Pause => vec![
+                Key(KeyCode::KeyP),
+                Key(KeyCode::Escape),
+                Gamepad(GamepadButton::Start),
+            ],
+            Action::Back => vec![Key(KeyCode::Escape), Gamepad(GamepadButton::East)],
+            Action::ToggleDebug => vec![Key(KeyCode::Backquote)],
+            Action::SaveMaps => vec![Key(KeyCode::F5)],
+            Action::MapOverview => vec![Key(KeyCode::F6)],
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Action::MoveUp => "Move Up",
+            Action::MoveDown => "Move Down",
+            Action::MoveLeft => "Move Left",
+            Action::MoveRight => "Move Right",
+            Action::Dash => "Dash",
+            Action::Interact => "Interact",
+            Action::ZoomIn => "Zoom In",
+            Action::ZoomOut => "Zoom Out",
+            Action::Pause => "Pause",
+            Action::Back => "Back",
+            Action::ToggleDebug => "Toggle Debug",
+            Action::SaveMaps => "Save Maps",
+            Action::MapOverview => "Map Overview",
+        })
+    }
+}
+
+/// A button that can trigger an [`Action`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// A button of any connected gamepad.
+    Gamepad(GamepadButton),
+}
+
+impl Binding {
+    fn pressed(
+        &self,
+        keys: &ButtonInput<KeyCode>,
+        mouse: &ButtonInput<MouseButton>,
+        gamepads: &Query<&Gamepad>,
+    ) -> bool {
+        match *self {
+            Binding::Key(key) => keys.pressed(key),
+            Binding::Mouse(button) => mouse.pressed(button),
+            Binding::Gamepad(button) => gamepads.iter().any(|gamepad| gamepad.pressed(button)),
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{key:?}");
+                let name = name
+                    .strip_prefix("Key")
+                    .or_else(|| name.strip_prefix("Digit"))
+                    .unwrap_or(&name);
+                f.write_str(name)
+            }
+            Binding::Mouse(button) => write!(f, "Mouse {button:?}"),
+            Binding::Gamepad(button) => write!(f, "Pad {button:?}"),
+        }
+    }
+}
+
+/// The buttons bound to each [`Action`].
+#[derive(Resource, Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct InputBindings {
+    pub bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_bindings()))
+                .collect(),
+        }
+    }
+}
+
+impl InputBindings {
+    /// The bindings of an action.
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Puts `binding` in the `slot`-th place of the bindings of `action`, or removes what is
+    /// there if `binding` is `None`. A binding that is already in another slot moves.
+    pub fn set(&mut self, action: Action, slot: usize, binding: Option<Binding>) {
+        let action_bindings = self.bindings.entry(action).or_default();
+        let slot = match binding.and_then(|b| action_bindings.iter().position(|&o| o == b)) {
+            Some(previous) => {
+                action_bindings.remove(previous);
+                slot.min(action_bindings.len())
+            }
+            None => slot,
+        };
+        match binding {
+            Some(binding) if slot < action_bindings.len() => action_bindings[slot] = binding,
+            Some(binding) => action_bindings.push(binding),
+            None if slot < action_bindings.len() => {
+                action_bindings.remove(slot);
+            }
+            None => {}
+        }
+    }
+
+    /// Removes `binding` from the given actions, returning the ones that had it.
+    pub fn unbind(&mut self, binding: Binding, actions: &[Action]) -> Vec<Action> {
+        actions
+            .iter()
+            .copied()
+            .filter(|action| {
+                self.bindings.get_mut(action).is_some_and(|bindings| {
+                    let len = bindings.len();
+                    bindings.retain(|&b| b != binding);
+                    bindings.len() != len
+                })
+            })
+            .collect()
+    }
+
+    /// The bindings saved at [`BINDINGS_PATH`], or the default ones if there are none or they
+    /// cannot be read. Actions missing from the file keep their default bindings.
+    pub fn load() -> Self {
+        #[cfg(not(target_family = "wasm"))]
+        match config::read::<Self>(BINDINGS_PATH) {
+            Ok(Some(mut bindings)) => {
+                for action in Action::ALL {
+                    bindings
+                        .bindings
+                        .entry(action)
+                        .or_insert_with(|| action.default_bindings());
+                }
+                return bindings;
+            }
+            Ok(None) => {}
+            Err(err) => warn!("Using the default input bindings: {err}"),
+        }
+        Self::default()
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn save_bindings(bindings: Res<InputBindings>) {
+    if let Err(err) = config::write(BINDINGS_PATH, &*bindings) {
+        error!("{err}");
+    }
+}
+
+/// Which actions are held, and which started being held this frame.
+#[derive(Resource, Debug, Default)]
+pub struct ActionState {
+    /// While set, no action is reported, e.g. while a new binding is being captured.
+    pub suspended: bool,
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+}
+
+fn update_action_state(
+    bindings: Res<InputBindings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut state: ResMut<ActionState>,
+) {
+    if state.suspended {
+        state.pressed.clear();
+        state.just_pressed.clear();
+        return;
+    }
+
+    let pressed: HashSet<Action> = bindings
+        .bindings
+        .iter()
+        .filter(|(_, bindings)| {
+            bindings
+                .iter()
+                .any(|binding| binding.pressed(&keys, &mouse, &gamepads))
+        })
+        .map(|(action, _)| *action)
+        .collect();
+    state.just_pressed = pressed.difference(&state.pressed).copied().collect();
+    state.pressed = pressed;
+}
+
+/// Run condition that is true on the frame `action` starts being held.
+pub fn action_just_pressed(action: Action) -> impl FnMut(Res<ActionState>) -> bool + Clone {
+    move |state: Res<ActionState>| state.just_pressed(action)
+}
@@ -5,9 +5,12 @@
 
 mod asset_tracking;
 mod audio;
+#[cfg(not(target_family = "wasm"))]
+mod config;
 mod demo;
 #[cfg(feature = "dev")]
 mod dev_tools;
+mod input;
 mod menus;
 mod screens;
 mod theme;
@@ -66,6 +69,7 @@ impl Plugin for AppPlugin {
             demo::plugin,
             #[cfg(feature = "dev")]
             dev_tools::plugin,
+            input::plugin,
             menus::plugin,
             screens::plugin,
             theme::plugin,
@@ -81,14 +85,28 @@ impl Plugin for AppPlugin {
             )
                 .chain(),
         );
+        app.configure_sets(
+            FixedUpdate,
+            (
+                AppSystems::TickTimers,
+                AppSystems::RecordInput,
+                AppSystems::Update,
+            )
+                .chain(),
+        );
 
         // Set up the `Pause` state.
         app.init_state::<Pause>();
         app.configure_sets(Update, PausableSystems.run_if(in_state(Pause(false))));
+        app.configure_sets(FixedUpdate, PausableSystems.run_if(in_state(Pause(false))));
+        app.add_systems(OnEnter(Pause(true)), pause_physics);
+        app.add_systems(OnExit(Pause(true)), unpause_physics);
     }
 }
 
-/// High-level groupings of systems for the app in the `Update` schedule.
+/// High-level groupings of systems for the app in the `Update` and `FixedUpdate` schedules.
+/// Movement and anything else feeding the physics runs in `FixedUpdate`, so that it steps
+/// along with it.
 /// When adding a new variant, make sure to order it in the `configure_sets`
 /// call above.
 #[derive(SystemSet, Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
@@ -108,3 +126,12 @@ struct Pause(pub bool);
 /// A system set for systems that shouldn't run while the game is paused.
 #[derive(SystemSet, Copy, Clone, Eq, PartialEq, Hash, Debug)]
 struct PausableSystems;
+
+/// Keep bodies from drifting with their last velocity while the game is paused.
+fn pause_physics(mut time: ResMut<Time<Physics>>) {
+    time.pause();
+}
+
+fn unpause_physics(mut time: ResMut<Time<Physics>>) {
+    time.unpause();
+}
@@ -0,0 +1,256 @@
+//! The controls menu, to rebind the gameplay actions.
+
+use bevy::prelude::*;
+
+use crate::{
+    input::{Action, ActionState, Binding, InputBindings, action_just_pressed},
+    menus::Menu,
+    theme::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<RebindCapture>();
+    app.add_systems(OnEnter(Menu::Controls), spawn_controls_menu);
+    app.add_systems(OnExit(Menu::Controls), stop_capture);
+    app.add_systems(
+        Update,
+        go_back.run_if(in_state(Menu::Controls).and(action_just_pressed(Action::Back))),
+    );
+    app.add_systems(
+        Update,
+        (capture_binding, update_binding_labels, update_hint_label)
+            .chain()
+            .run_if(in_state(Menu::Controls)),
+    );
+}
+
+/// The actions listed in the menu. Bindings only conflict with each other within this list, so
+/// that e.g. `Escape` can both pause the game and leave menus.
+const REBINDABLE_ACTIONS: [Action; 9] = [
+    Action::MoveUp,
+    Action::MoveDown,
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::Dash,
+    Action::Interact,
+    Action::ZoomIn,
+    Action::ZoomOut,
+    Action::Pause,
+];
+
+/// How many bindings are shown per action: enough for the action with the most default ones.
+fn binding_slots() -> usize {
+    Action::ALL
+        .into_iter()
+        .map(|action| action.default_bindings().len())
+        .max()
+        .unwrap_or(1)
+}
+
+const DEFAULT_HINT: &str = "Click a binding to change it.";
+
+/// The binding waiting for a key or button press, and what to tell the player.
+#[derive(Resource, Debug, Default)]
+struct RebindCapture {
+    slot: Option<(Action, usize)>,
+    hint: Option<String>,
+}
+
+/// The button showing the `slot`-th binding of `action`.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+struct BindingSlot {
+    action: Action,
+    slot: usize,
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct HintLabel;
+
+fn spawn_controls_menu(mut commands: Commands) {
+    let slots = binding_slots();
+    let root = commands
+        .spawn((
+            widget::ui_root("Controls Menu"),
+            GlobalZIndex(2),
+            DespawnOnExit(Menu::Controls),
+            children![widget::header("Controls")],
+        ))
+        .id();
+    commands
+        .spawn((
+            Name::new("Controls Grid"),
+            Node {
+                display: Display::Grid,
+                row_gap: px(6),
+                column_gap: px(20),
+                // The action name, then its bindings.
+                grid_template_columns: RepeatedGridTrack::px(slots as u16 + 1, 220.0),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ChildOf(root),
+        ))
+        .with_children(|parent| {
+            for action in REBINDABLE_ACTIONS {
+                parent.spawn((
+                    widget::label(action.to_string()),
+                    Node {
+                        justify_self: JustifySelf::End,
+                        ..default()
+                    },
+                ));
+                for slot in 0..slots {
+                    parent.spawn((
+                        widget::button_medium(
+                            "",
+                            move |_: On<Pointer<Click>>,
+                                  mut capture: ResMut<RebindCapture>,
+                                  mut actions: ResMut<ActionState>| {
+                                capture.slot = Some((action, slot));
+                                capture.hint = None;
+                                actions.suspended = true;
+                            },
+                        ),
+                        BindingSlot { action, slot },
+                    ));
+                }
+            }
+        });
+    commands.spawn((widget::label(DEFAULT_HINT), HintLabel, ChildOf(root)));
+    commands.spawn((
+        widget::button("Reset to defaults", reset_bindings),
+        ChildOf(root),
+    ));
+    commands.spawn((widget::button("Back", go_back_on_click), ChildOf(root)));
+}
+
+/// Binds the next key, mouse button or gamepad button pressed. `Escape` cancels, `Delete` and
+/// `Backspace` clear the binding. The left mouse button is left to the UI.
+fn capture_binding(
+    mut capture: ResMut<RebindCapture>,
+    mut actions: ResMut<ActionState>,
+    mut bindings: ResMut<InputBindings>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut mouse: ResMut<ButtonInput<MouseButton>>,
+    mut gamepads: Query<&mut Gamepad>,
+) {
+    let Some((action, slot)) = capture.slot else {
+        return;
+    };
+
+    let key = keys.get_just_pressed().next().copied();
+    let mouse_button = mouse
+        .get_just_pressed()
+        .find(|&&button| button != MouseButton::Left)
+        .copied();
+    let gamepad_button = gamepads.iter_mut().find_map(|mut gamepad| {
+        let button = gamepad.get_just_pressed().next().copied()?;
+        // Don't let the press trigger its new action as soon as capture ends.
+        gamepad.digital_mut().reset(button);
+        Some(button)
+    });
+
+    let binding = match (key, mouse_button, gamepad_button) {
+        (Some(key), ..) => {
+            keys.reset(key);
+            match key {
+                KeyCode::Escape => {
+                    capture.slot = None;
+                    actions.suspended = false;
+                    return;
+                }
+                KeyCode::Delete | KeyCode::Backspace => None,
+                key => Some(Binding::Key(key)),
+            }
+        }
+        (None, Some(button), _) => {
+            mouse.reset(button);
+            Some(Binding::Mouse(button))
+        }
+        (None, None, Some(button)) => Some(Binding::Gamepad(button)),
+        (None, None, None) => return,
+    };
+
+    capture.slot = None;
+    actions.suspended = false;
+    let Some(binding) = binding else {
+        bindings.set(action, slot, None);
+        return;
+    };
+    let others: Vec<Action> = REBINDABLE_ACTIONS
+        .into_iter()
+        .filter(|&other| other != action)
+        .collect();
+    let conflicts = bindings.unbind(binding, &others);
+    bindings.set(action, slot, Some(binding));
+    if !conflicts.is_empty() {
+        let names: Vec<String> = conflicts.iter().map(ToString::to_string).collect();
+        capture.hint = Some(format!(
+            "{binding} was bound to {}, it now only does {action}.",
+            names.join(", ")
+        ));
+    }
+}
+
+fn update_binding_labels(
+    capture: Res<RebindCapture>,
+    bindings: Res<InputBindings>,
+    slot_query: Query<(Entity, &BindingSlot)>,
+    children_query: Query<&Children>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (entity, binding_slot) in &slot_query {
+        let text = if capture.slot == Some((binding_slot.action, binding_slot.slot)) {
+            "...".to_string()
+        } else {
+            bindings
+                .get(binding_slot.action)
+                .get(binding_slot.slot)
+                .map_or_else(|| "-".to_string(), ToString::to_string)
+        };
+        for descendant in children_query.iter_descendants(entity) {
+            if let Ok(mut label) = text_query.get_mut(descendant)
+                && label.0 != text
+            {
+                label.0.clone_from(&text);
+            }
+        }
+    }
+}
+
+fn update_hint_label(capture: Res<RebindCapture>, mut label: Single<&mut Text, With<HintLabel>>) {
+    if !capture.is_changed() {
+        return;
+    }
+    label.0 = match (&capture.slot, &capture.hint) {
+        (Some((action, _)), _) => format!(
+            "Press a key or button for {action}. Escape cancels, Delete clears the binding."
+        ),
+        (None, Some(hint)) => hint.clone(),
+        (None, None) => DEFAULT_HINT.to_string(),
+    };
+}
+
+fn reset_bindings(
+    _: On<Pointer<Click>>,
+    mut bindings: ResMut<InputBindings>,
+    mut capture: ResMut<RebindCapture>,
+) {
+    *bindings = InputBindings::default();
+    capture.hint = None;
+}
+
+fn stop_capture(mut capture: ResMut<RebindCapture>, mut actions: ResMut<ActionState>) {
+    *capture = RebindCapture::default();
+    actions.suspended = false;
+}
+
+fn go_back_on_click(_: On<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Settings);
+}
+
+fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Settings);
+}
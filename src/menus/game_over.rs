@@ -0,0 +1,31 @@
+//! The game over menu (seen on the game over screen).
+
+use bevy::prelude::*;
+
+use crate::{menus::Menu, screens::Screen, theme::widget};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Menu::GameOver), spawn_game_over_menu);
+}
+
+fn spawn_game_over_menu(mut commands: Commands) {
+    commands.spawn((
+        widget::ui_root("Game Over Menu"),
+        GlobalZIndex(2),
+        DespawnOnExit(Menu::GameOver),
+        children![
+            widget::header("Game over"),
+            widget::button("Retry", retry),
+            widget::button("Quit to title", quit_to_title),
+        ],
+    ));
+}
+
+/// The map is built again on the loading screen.
+fn retry(_: On<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Loading);
+}
+
+fn quit_to_title(_: On<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
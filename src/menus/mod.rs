@@ -1,6 +1,8 @@
 //! The game's menus and transitions between them.
 
+mod controls;
 mod credits;
+mod game_over;
 mod main;
 mod pause;
 mod settings;
@@ -11,7 +13,9 @@ pub(super) fn plugin(app: &mut App) {
     app.init_state::<Menu>();
 
     app.add_plugins((
+        controls::plugin,
         credits::plugin,
+        game_over::plugin,
         main::plugin,
         settings::plugin,
         pause::plugin,
@@ -25,5 +29,7 @@ pub enum Menu {
     Main,
     Credits,
     Settings,
+    Controls,
     Pause,
+    GameOver,
 }
@@ -1,24 +1,102 @@
 //! The settings menu.
 //!
 //! Additional settings and accessibility options should go here.
+//!
+//! The toggles are read from [`SETTINGS_PATH`] on native builds, and written back when they are
+//! changed from this menu.
 
-use bevy::{audio::Volume, input::common_conditions::input_just_pressed, prelude::*};
+use bevy::{audio::Volume, prelude::*};
+use serde::{Deserialize, Serialize};
 
-use crate::{menus::Menu, screens::Screen, theme::prelude::*};
+#[cfg(not(target_family = "wasm"))]
+use crate::config;
+use crate::{
+    input::{Action, action_just_pressed},
+    menus::Menu,
+    screens::Screen,
+    theme::prelude::*,
+    utils::cam::{CameraShakeSettings, PixelPerfect},
+};
 
 pub(super) fn plugin(app: &mut App) {
+    SavedSettings::load().apply(app.world_mut());
+
     app.add_systems(OnEnter(Menu::Settings), spawn_settings_menu);
     app.add_systems(
         Update,
-        go_back.run_if(in_state(Menu::Settings).and(input_just_pressed(KeyCode::Escape))),
+        go_back.run_if(in_state(Menu::Settings).and(action_just_pressed(Action::Back))),
     );
 
     app.add_systems(
         Update,
-        update_global_volume_label.run_if(in_state(Menu::Settings)),
+        (
+            update_global_volume_label,
+            update_screen_shake_label,
+            update_pixel_perfect_label,
+        )
+            .run_if(in_state(Menu::Settings)),
     );
 }
 
+/// Where the settings are stored, relative to the working directory.
+pub const SETTINGS_PATH: &str = "config/settings.ron";
+
+/// The settings that are kept between runs. Missing ones keep their default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedSettings {
+    pub screen_shake: bool,
+    pub pixel_perfect: bool,
+}
+
+impl Default for SavedSettings {
+    fn default() -> Self {
+        Self {
+            screen_shake: CameraShakeSettings::default().enabled,
+            pixel_perfect: PixelPerfect::default().enabled,
+        }
+    }
+}
+
+impl SavedSettings {
+    /// The settings saved at [`SETTINGS_PATH`], or the default ones if there are none or they
+    /// cannot be read.
+    pub fn load() -> Self {
+        #[cfg(not(target_family = "wasm"))]
+        match config::read::<Self>(SETTINGS_PATH) {
+            Ok(Some(settings)) => return settings,
+            Ok(None) => {}
+            Err(err) => warn!("Using the default settings: {err}"),
+        }
+        Self::default()
+    }
+
+    /// The settings currently in use.
+    pub fn current(world: &mut World) -> Self {
+        Self {
+            screen_shake: world.get_resource_or_init::<CameraShakeSettings>().enabled,
+            pixel_perfect: world.get_resource_or_init::<PixelPerfect>().enabled,
+        }
+    }
+
+    /// Puts the settings in use.
+    pub fn apply(&self, world: &mut World) {
+        world.get_resource_or_init::<CameraShakeSettings>().enabled = self.screen_shake;
+        world.get_resource_or_init::<PixelPerfect>().enabled = self.pixel_perfect;
+    }
+}
+
+/// Writes the settings in use to [`SETTINGS_PATH`], e.g. `commands.queue(save_settings)` after
+/// changing one. Does nothing on the web.
+fn save_settings(world: &mut World) {
+    #[cfg(not(target_family = "wasm"))]
+    if let Err(err) = config::write(SETTINGS_PATH, &SavedSettings::current(world)) {
+        error!("{err}");
+    }
+    #[cfg(target_family = "wasm")]
+    let _ = world;
+}
+
 fn spawn_settings_menu(mut commands: Commands) {
     commands.spawn((
         widget::ui_root("Settings Menu"),
@@ -27,6 +105,7 @@ fn spawn_settings_menu(mut commands: Commands) {
         children![
             widget::header("Settings"),
             settings_grid(),
+            widget::button("Controls", open_controls_menu),
             widget::button("Back", go_back_on_click),
         ],
     ));
@@ -51,6 +130,22 @@ fn settings_grid() -> impl Bundle {
                 }
             ),
             global_volume_widget(),
+            (
+                widget::label("Screen Shake"),
+                Node {
+                    justify_self: JustifySelf::End,
+                    ..default()
+                }
+            ),
+            screen_shake_widget(),
+            (
+                widget::label("Pixel Perfect"),
+                Node {
+                    justify_self: JustifySelf::End,
+                    ..default()
+                }
+            ),
+            pixel_perfect_widget(),
         ],
     )
 }
@@ -103,6 +198,108 @@ fn update_global_volume_label(
     label.0 = format!("{percent:3.0}%");
 }
 
+fn screen_shake_widget() -> impl Bundle {
+    (
+        Name::new("Screen Shake Widget"),
+        Node {
+            justify_self: JustifySelf::Start,
+            ..default()
+        },
+        children![(
+            widget::button_medium("", toggle_screen_shake),
+            ScreenShakeToggle
+        )],
+    )
+}
+
+fn toggle_screen_shake(
+    _: On<Pointer<Click>>,
+    mut commands: Commands,
+    mut settings: ResMut<CameraShakeSettings>,
+) {
+    settings.enabled = !settings.enabled;
+    commands.queue(save_settings);
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct ScreenShakeToggle;
+
+fn update_screen_shake_label(
+    settings: Res<CameraShakeSettings>,
+    toggle: Single<Entity, With<ScreenShakeToggle>>,
+    children_query: Query<&Children>,
+    mut text_query: Query<&mut Text>,
+) {
+    set_button_text(
+        *toggle,
+        if settings.enabled { "On" } else { "Off" },
+        &children_query,
+        &mut text_query,
+    );
+}
+
+fn pixel_perfect_widget() -> impl Bundle {
+    (
+        Name::new("Pixel Perfect Widget"),
+        Node {
+            justify_self: JustifySelf::Start,
+            ..default()
+        },
+        children![(
+            widget::button_medium("", toggle_pixel_perfect),
+            PixelPerfectToggle
+        )],
+    )
+}
+
+fn toggle_pixel_perfect(
+    _: On<Pointer<Click>>,
+    mut commands: Commands,
+    mut pixel_perfect: ResMut<PixelPerfect>,
+) {
+    pixel_perfect.enabled = !pixel_perfect.enabled;
+    commands.queue(save_settings);
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct PixelPerfectToggle;
+
+fn update_pixel_perfect_label(
+    pixel_perfect: Res<PixelPerfect>,
+    toggle: Single<Entity, With<PixelPerfectToggle>>,
+    children_query: Query<&Children>,
+    mut text_query: Query<&mut Text>,
+) {
+    set_button_text(
+        *toggle,
+        if pixel_perfect.enabled { "On" } else { "Off" },
+        &children_query,
+        &mut text_query,
+    );
+}
+
+/// Sets the text of the button `entity`, which is nested in the button's node.
+fn set_button_text(
+    entity: Entity,
+    text: &str,
+    children_query: &Query<&Children>,
+    text_query: &mut Query<&mut Text>,
+) {
+    for descendant in children_query.iter_descendants(entity) {
+        if let Ok(mut button_text) = text_query.get_mut(descendant)
+            && button_text.0 != text
+        {
+            button_text.0 = text.to_string();
+        }
+    }
+}
+
+fn open_controls_menu(_: On<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Controls);
+}
+
 fn go_back_on_click(
     _: On<Pointer<Click>>,
     screen: Res<State<Screen>>,
@@ -1,30 +1,34 @@
 //! The screen state for the main gameplay.
 
-use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy::prelude::*;
 
 use crate::{
-    Pause, demo::level::spawn_level, menus::Menu, screens::Screen, utils::tiled::spawn_tiled_map,
+    Pause,
+    demo::level::spawn_level,
+    input::{Action, action_just_pressed},
+    menus::Menu,
+    screens::Screen,
 };
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(
-        OnEnter(Screen::Gameplay),
-        (spawn_tiled_map::<1>, spawn_level).chain(),
-    );
+    // The map is built on the loading screen.
+    app.add_systems(OnEnter(Screen::Gameplay), spawn_level);
 
-    // Toggle pause on key press.
+    // Toggle pause on the pause action.
     app.add_systems(
         Update,
         (
             (pause, spawn_pause_overlay, open_pause_menu).run_if(
                 in_state(Screen::Gameplay)
                     .and(in_state(Menu::None))
-                    .and(input_just_pressed(KeyCode::KeyP).or(input_just_pressed(KeyCode::Escape))),
+                    .and(action_just_pressed(Action::Pause)),
             ),
+            // Only from the pause menu: the deeper menus share `Escape` with their back action,
+            // which already goes back one level.
             close_menu.run_if(
                 in_state(Screen::Gameplay)
-                    .and(not(in_state(Menu::None)))
-                    .and(input_just_pressed(KeyCode::KeyP)),
+                    .and(in_state(Menu::Pause))
+                    .and(action_just_pressed(Action::Pause)),
             ),
         ),
     );
@@ -1,16 +1,28 @@
-//! A loading screen during which game assets are loaded if necessary.
+//! A loading screen during which game assets are loaded and the map is built.
 //! This reduces stuttering, especially for audio on Wasm.
 
 use bevy::prelude::*;
 
-use crate::{asset_tracking::ResourceHandles, screens::Screen, theme::prelude::*};
+use crate::{
+    asset_tracking::ResourceHandles,
+    screens::Screen,
+    theme::prelude::*,
+    utils::tiled::{MapSpawnProgress, all_maps_built, spawn_tiled_map},
+};
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(OnEnter(Screen::Loading), spawn_loading_screen);
+    app.add_systems(
+        OnEnter(Screen::Loading),
+        (spawn_loading_screen, spawn_tiled_map::<1>),
+    );
 
     app.add_systems(
         Update,
-        enter_gameplay_screen.run_if(in_state(Screen::Loading).and(all_assets_loaded)),
+        (
+            update_loading_label,
+            enter_gameplay_screen.run_if(all_assets_loaded.and(all_maps_built)),
+        )
+            .run_if(in_state(Screen::Loading)),
     );
 }
 
@@ -18,10 +30,30 @@ fn spawn_loading_screen(mut commands: Commands) {
     commands.spawn((
         widget::ui_root("Loading Screen"),
         DespawnOnExit(Screen::Loading),
-        children![widget::label("Loading...")],
+        children![(widget::label("Loading..."), LoadingLabel)],
     ));
 }
 
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct LoadingLabel;
+
+fn update_loading_label(
+    progress_query: Query<&MapSpawnProgress>,
+    mut label: Single<&mut Text, With<LoadingLabel>>,
+) {
+    let (sum, count) = progress_query
+        .iter()
+        .fold((0.0, 0), |(sum, count), progress| {
+            (sum + progress.fraction(), count + 1)
+        });
+    if count == 0 {
+        return;
+    }
+    let percent = 100.0 * sum / count as f32;
+    label.0 = format!("Loading... {percent:3.0}%");
+}
+
 fn enter_gameplay_screen(mut next_screen: ResMut<NextState<Screen>>) {
     next_screen.set(Screen::Gameplay);
 }
@@ -1,5 +1,6 @@
 //! The game's main screen states and transitions between them.
 
+mod game_over;
 mod gameplay;
 mod loading;
 mod splash;
@@ -11,6 +12,7 @@ pub(super) fn plugin(app: &mut App) {
     app.init_state::<Screen>();
 
     app.add_plugins((
+        game_over::plugin,
         gameplay::plugin,
         loading::plugin,
         splash::plugin,
@@ -26,4 +28,5 @@ pub enum Screen {
     Title,
     Loading,
     Gameplay,
+    GameOver,
 }
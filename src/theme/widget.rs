@@ -58,6 +58,7 @@ where
     button_base(
         text,
         action,
+        40.0,
         Node {
             width: px(380),
             height: px(80),
@@ -69,6 +70,27 @@ where
     )
 }
 
+/// A medium button with smaller text and an action defined as an [`Observer`], for dense lists.
+pub fn button_medium<E, B, M, I>(text: impl Into<String>, action: I) -> impl Bundle
+where
+    E: EntityEvent,
+    B: Bundle,
+    I: IntoObserverSystem<E, B, M>,
+{
+    button_base(
+        text,
+        action,
+        24.0,
+        Node {
+            width: px(220),
+            height: px(36),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+    )
+}
+
 /// A small square button with text and an action defined as an [`Observer`].
 pub fn button_small<E, B, M, I>(text: impl Into<String>, action: I) -> impl Bundle
 where
@@ -79,6 +101,7 @@ where
     button_base(
         text,
         action,
+        40.0,
         Node {
             width: px(30),
             height: px(30),
@@ -93,6 +116,7 @@ where
 fn button_base<E, B, M, I>(
     text: impl Into<String>,
     action: I,
+    font_size: f32,
     button_bundle: impl Bundle,
 ) -> impl Bundle
 where
@@ -119,7 +143,7 @@ where
                     children![(
                         Name::new("Button Text"),
                         Text(text),
-                        TextFont::from_font_size(40.0),
+                        TextFont::from_font_size(font_size),
                         TextColor(BUTTON_TEXT),
                         // Don't bubble picking events from the text up to the button.
                         Pickable::IGNORE,
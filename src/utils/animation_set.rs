@@ -0,0 +1,404 @@
+//! Sprite sheet animations described in data instead of code.
+//!
+//! An `.anim.ron` file names the sheet, how it is cut into frames and which frames make up the
+//! clip of each state and [`Direction`]:
+//!
+//! ```ron
+//! (
+//!     image: "eris_esra-template-20x20.png",
+//!     grid: (tile_size: (20, 20), columns: 5, rows: 15),
+//!     clips: {
+//!         "idle": {
+//!             Down: (frames: [0, 1, 2], frame_duration: 0.5),
+//!             Right: (frames: [10, 11, 12], frame_duration: 0.5),
+//!         },
+//!         "walk": {
+//!             // Sends an `AnimationEvent` named "step" each time the second frame shows.
+//!             Down: (frames: [25, 26, 27, 28], frame_duration: 0.1, events: { 1: "step" }),
+//!         },
+//!     },
+//!     // Left uses the clip of Right, flipped horizontally.
+//!     mirror_x: { Left: Right },
+//!     // Optional named rectangles, e.g. hitboxes, in pixels from the top left of the frame.
+//!     // Each key starts at a frame of the sheet, i.e. an atlas index, not a frame of a clip.
+//!     slices: {
+//!         "hitbox": [(frame: 0, bounds: (min: (6, 4), max: (14, 20)))],
+//!     },
+//! )
+//! ```
+//!
+//! With the `aseprite` feature, Aseprite JSON exports can be loaded as well, see
+//! [`crate::utils::aseprite`]. An [`Animator`] plays the clips.
+//!
+//! [`Animator`]: crate::utils::animator::Animator
+
+use std::{f32::consts::FRAC_PI_4, time::Duration};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, ParseAssetPathError, io::Reader},
+    platform::collections::HashMap,
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<AnimationSet>();
+    app.register_asset_loader(AnimationSetLoader);
+}
+
+#[derive(Clone, Copy, Debug, Reflect, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    #[default]
+    Down,
+    Left,
+    Right,
+    UpRight,
+    UpLeft,
+    DownRight,
+    DownLeft,
+    Nothing, // ex. Vec2::ZERO
+}
+
+impl From<Vec2> for Direction {
+    /// The closest of the 8 directions by angle, or [`Direction::Nothing`] for a zero or
+    /// non-finite vector.
+    fn from(v: Vec2) -> Self {
+        use Direction::*;
+        if v == Vec2::ZERO || !v.is_finite() {
+            return Nothing;
+        }
+        // Each direction covers the 45° around it, counterclockwise from the right.
+        let octant = (v.y.atan2(v.x) / FRAC_PI_4).round() as i32;
+        match octant.rem_euclid(8) {
+            0 => Right,
+            1 => UpRight,
+            2 => Up,
+            3 => UpLeft,
+            4 => Left,
+            5 => DownLeft,
+            6 => Down,
+            _ => DownRight,
+        }
+    }
+}
+
+/// How the sheet is cut into frames, see [`TextureAtlasLayout::from_grid`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnimationGrid {
+    pub tile_size: UVec2,
+    pub columns: u32,
+    pub rows: u32,
+    #[serde(default)]
+    pub padding: Option<UVec2>,
+    #[serde(default)]
+    pub offset: Option<UVec2>,
+}
+
+/// A sequence of frames of the sheet.
+#[derive(Debug, Clone, Reflect, Serialize, Deserialize)]
+pub struct AnimationClip {
+    /// Indices in the texture atlas.
+    pub frames: Vec<usize>,
+    /// Seconds each frame is shown.
+    pub frame_duration: f32,
+    /// Seconds each frame is shown, overriding `frame_duration` for the frames it covers.
+    #[serde(default)]
+    pub frame_durations: Vec<f32>,
+    /// Whether the clip starts over after its last frame, or stays on it.
+    #[serde(default = "looping_default")]
+    pub looping: bool,
+    /// Names of the [`AnimationEvent`]s sent when a frame shows, by frame.
+    ///
+    /// [`AnimationEvent`]: crate::utils::animator::AnimationEvent
+    #[serde(default)]
+    pub events: HashMap<usize, String>,
+}
+
+fn looping_default() -> bool {
+    true
+}
+
+impl AnimationClip {
+    /// The atlas index of a frame. Frames past the end show the last one.
+    pub fn atlas_index(&self, frame: usize) -> usize {
+        self.frames
+            .get(frame)
+            .or(self.frames.last())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// How long a frame is shown.
+    pub fn duration(&self, frame: usize) -> Duration {
+        Duration::from_secs_f32(
+            self.frame_durations
+                .get(frame)
+                .copied()
+                .unwrap_or(self.frame_duration)
+                .max(0.0),
+        )
+    }
+
+    /// The frame after `frame`, or `None` at the end of a clip that doesn't loop.
+    pub fn next_frame(&self, frame: usize) -> Option<usize> {
+        if frame + 1 < self.frames.len() {
+            Some(frame + 1)
+        } else if self.looping {
+            Some(0)
+        } else {
+            None
+        }
+    }
+}
+
+/// A named rectangle of the frames, starting at a frame of the sheet and holding for the following
+/// ones until the next key.
+#[derive(Debug, Clone, Reflect, Serialize, Deserialize)]
+pub struct AnimationSlice {
+    /// Index in the texture atlas of the first frame the key applies to. Clips can show the sheet
+    /// frames in any order, so this is not a frame of a clip.
+    pub frame: usize,
+    /// In pixels from the top left of the frame.
+    pub bounds: URect,
+}
+
+/// The animations of a sprite sheet, loaded from an `.anim.ron` file.
+#[derive(Asset, TypePath, Debug)]
+pub struct AnimationSet {
+    #[dependency]
+    pub image: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    /// Size of a frame, in pixels.
+    pub frame_size: UVec2,
+    /// Clips by state name, then by direction. Clips that don't depend on the direction go
+    /// under [`Direction::Nothing`].
+    pub clips: HashMap<String, HashMap<Direction, AnimationClip>>,
+    /// Directions without clips of their own that use the clip of another direction, flipped
+    /// horizontally.
+    pub mirror_x: HashMap<Direction, Direction>,
+    /// Keys of each slice, by frame.
+    pub slices: HashMap<String, Vec<AnimationSlice>>,
+}
+
+impl AnimationSet {
+    /// The clip to play for a state and direction, and whether to flip it horizontally.
+    pub fn clip(&self, state: &str, direction: Direction) -> Option<(&AnimationClip, bool)> {
+        let clips = self.clips.get(state)?;
+        if let Some(clip) = clips.get(&direction) {
+            return Some((clip, false));
+        }
+        if let Some(clip) = self
+            .mirror_x
+            .get(&direction)
+            .and_then(|mirrored| clips.get(mirrored))
+        {
+            return Some((clip, true));
+        }
+        clips.get(&Direction::Nothing).map(|clip| (clip, false))
+    }
+
+    /// The rectangle of a slice on the sheet frame `atlas_index`, e.g. the index of the texture
+    /// atlas of the sprite, relative to the center of the sprite with y up, as a collider would
+    /// need. `flip_x` mirrors it like [`Sprite::flip_x`].
+    pub fn slice(&self, name: &str, atlas_index: usize, flip_x: bool) -> Option<Rect> {
+        let key = self
+            .slices
+            .get(name)?
+            .iter()
+            .filter(|key| key.frame <= atlas_index)
+            .max_by_key(|key| key.frame)?;
+        let half_size = self.frame_size.as_vec2() / 2.0;
+        let mut min = key.bounds.min.as_vec2() - half_size;
+        let mut max = key.bounds.max.as_vec2() - half_size;
+        (min.y, max.y) = (-max.y, -min.y);
+        if flip_x {
+            (min.x, max.x) = (-max.x, -min.x);
+        }
+        Some(Rect::from_corners(min, max))
+    }
+
+    /// A sprite showing the first frame of the sheet.
+    pub fn sprite(&self) -> Sprite {
+        Sprite::from_atlas_image(
+            self.image.clone(),
+            TextureAtlas {
+                layout: self.layout.clone(),
+                index: 0,
+            },
+        )
+    }
+}
+
+/// The contents of an `.anim.ron` file.
+#[derive(Debug, Serialize, Deserialize)]
+struct AnimationSetFile {
+    /// Path of the sheet, relative to the file.
+    image: String,
+    grid: AnimationGrid,
+    clips: HashMap<String, HashMap<Direction, AnimationClip>>,
+    #[serde(default)]
+    mirror_x: HashMap<Direction, Direction>,
+    #[serde(default)]
+    slices: HashMap<String, Vec<AnimationSlice>>,
+}
+
+#[derive(Debug, Error)]
+pub enum AnimationSetLoaderError {
+    #[error("Could not load animation set: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse animation set: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("Invalid image path in animation set: {0}")]
+    ImagePath(#[from] ParseAssetPathError),
+}
+
+#[derive(TypePath)]
+pub struct AnimationSetLoader;
+
+impl AssetLoader for AnimationSetLoader {
+    type Asset = AnimationSet;
+    type Settings = ();
+    type Error = AnimationSetLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file: AnimationSetFile = ron::de::from_bytes(&bytes)?;
+
+        let image_path = load_context.path().resolve_embed(&file.image)?;
+        let image = load_context.load(image_path);
+        let layout = load_context.add_labeled_asset(
+            "layout".to_string(),
+            TextureAtlasLayout::from_grid(
+                file.grid.tile_size,
+                file.grid.columns,
+                file.grid.rows,
+                file.grid.padding,
+                file.grid.offset,
+            ),
+        );
+
+        Ok(AnimationSet {
+            image,
+            layout,
+            frame_size: file.grid.tile_size,
+            clips: file.clips,
+            mirror_x: file.mirror_x,
+            slices: file.slices,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["anim.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_8;
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// One direction per octant, counterclockwise from the right.
+    const OCTANTS: [Direction; 8] = [
+        Direction::Right,
+        Direction::UpRight,
+        Direction::Up,
+        Direction::UpLeft,
+        Direction::Left,
+        Direction::DownLeft,
+        Direction::Down,
+        Direction::DownRight,
+    ];
+
+    /// Keeps samples this far from the boundaries between octants, where rounding may go
+    /// either way.
+    const MARGIN: f32 = 1e-4;
+
+    fn non_finite() -> impl Strategy<Value = f32> {
+        prop_oneof![Just(f32::NAN), Just(f32::INFINITY), Just(f32::NEG_INFINITY)]
+    }
+
+    proptest! {
+        #[test]
+        fn any_angle_and_length_gives_the_closest_direction(
+            octant in 0usize..8,
+            offset in (-FRAC_PI_8 + MARGIN)..(FRAC_PI_8 - MARGIN),
+            length in 1e-6f32..1e6,
+        ) {
+            let angle = octant as f32 * FRAC_PI_4 + offset;
+            let v = Vec2::from_angle(angle) * length;
+            prop_assert_eq!(Direction::from(v), OCTANTS[octant]);
+        }
+
+        #[test]
+        fn non_zero_finite_vectors_have_a_direction(x in -1e30f32..1e30, y in -1e30f32..1e30) {
+            let v = Vec2::new(x, y);
+            prop_assume!(v != Vec2::ZERO);
+            prop_assert_ne!(Direction::from(v), Direction::Nothing);
+        }
+
+        #[test]
+        fn non_finite_vectors_have_no_direction(
+            finite in -1e6f32..1e6,
+            bad in non_finite(),
+            bad_x: bool,
+        ) {
+            let v = if bad_x {
+                Vec2::new(bad, finite)
+            } else {
+                Vec2::new(finite, bad)
+            };
+            prop_assert_eq!(Direction::from(v), Direction::Nothing);
+        }
+    }
+
+    #[test]
+    fn axes_and_diagonals_give_their_direction() {
+        let vectors = [
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(-1.0, 1.0),
+            Vec2::new(-1.0, 0.0),
+            Vec2::new(-1.0, -1.0),
+            Vec2::new(0.0, -1.0),
+            Vec2::new(1.0, -1.0),
+        ];
+        for (v, expected) in vectors.into_iter().zip(OCTANTS) {
+            assert_eq!(Direction::from(v), expected, "{v}");
+        }
+        // atan2 gives -180° rather than 180° here.
+        assert_eq!(Direction::from(Vec2::new(-1.0, -0.0)), Direction::Left);
+    }
+
+    #[test]
+    fn boundaries_are_halfway_between_directions() {
+        for octant in 0..8 {
+            let boundary = FRAC_PI_8 + octant as f32 * FRAC_PI_4;
+            assert_eq!(
+                Direction::from(Vec2::from_angle(boundary - 1e-3)),
+                OCTANTS[octant]
+            );
+            assert_eq!(
+                Direction::from(Vec2::from_angle(boundary + 1e-3)),
+                OCTANTS[(octant + 1) % 8]
+            );
+        }
+    }
+
+    #[test]
+    fn zero_vectors_have_no_direction() {
+        assert_eq!(Direction::from(Vec2::ZERO), Direction::Nothing);
+        assert_eq!(Direction::from(Vec2::new(-0.0, -0.0)), Direction::Nothing);
+    }
+}
@@ -0,0 +1,420 @@
+//! A sprite animation state machine for any entity with an [`AnimationSet`].
+//!
+//! States are the clip names of the set. An [`Animator`] loops its base state (`idle`, `walk`...)
+//! and can play one-shot clips over it (`attack`, `hurt`...) that return to the base state when
+//! they end, or at once if the set has no such clip. A transition can play an in-between clip when
+//! the base state changes, e.g. `land` between `fall` and `idle`. A base state whose clip doesn't
+//! loop stays on its last frame.
+//!
+//! Frames can send an [`AnimationEvent`], e.g. to play a step sound when a foot touches the
+//! ground, see [`AnimationClip::events`].
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    utils::animation_set::{AnimationClip, AnimationSet, Direction},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.configure_sets(
+        Update,
+        AnimatorSystems::Apply
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+    app.add_systems(
+        Update,
+        (
+            tick_animators
+                .in_set(AppSystems::TickTimers)
+                .in_set(PausableSystems),
+            (update_animator_atlas, trigger_animation_events).in_set(AnimatorSystems::Apply),
+        ),
+    );
+}
+
+/// Systems that change the state of an [`Animator`] should run before
+/// [`AnimatorSystems::Apply`] to show on the same frame.
+#[derive(SystemSet, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum AnimatorSystems {
+    /// Update the sprites and send the animation events.
+    Apply,
+}
+
+/// Plays the clips of an [`AnimationSet`] on the [`Sprite`] of the entity.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Animator {
+    set: Handle<AnimationSet>,
+    /// The state played when no one-shot clip is.
+    base: String,
+    /// A clip played once over the base state.
+    one_shot: Option<String>,
+    direction: Direction,
+    /// In-between clips played when the base state changes.
+    transitions: Vec<AnimationTransition>,
+    timer: Timer,
+    frame: usize,
+    /// Whether the frame or the state changed this tick.
+    changed: bool,
+    /// Whether a frame started showing this tick, as opposed to only the direction changing.
+    entered: bool,
+    /// The one-shot clip that ended this tick.
+    finished: Option<String>,
+}
+
+/// Plays the `via` clip once when the base state changes from `from` to `to`.
+#[derive(Debug, Clone, Reflect)]
+pub struct AnimationTransition {
+    pub from: String,
+    pub to: String,
+    pub via: String,
+}
+
+impl Animator {
+    pub fn new(set: Handle<AnimationSet>, base: impl Into<String>) -> Self {
+        Self {
+            set,
+            base: base.into(),
+            one_shot: None,
+            direction: Direction::Down,
+            transitions: Vec::new(),
+            timer: Timer::new(default(), TimerMode::Once),
+            frame: 0,
+            changed: true,
+            entered: true,
+            finished: None,
+        }
+    }
+
+    /// Plays `via` once when the base state changes from `from` to `to`.
+    pub fn with_transition(
+        mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        via: impl Into<String>,
+    ) -> Self {
+        self.transitions.push(AnimationTransition {
+            from: from.into(),
+            to: to.into(),
+            via: via.into(),
+        });
+        self
+    }
+
+    /// The clip playing: the one-shot clip if there is one, or else the base state.
+    pub fn state(&self) -> &str {
+        self.one_shot.as_deref().unwrap_or(&self.base)
+    }
+
+    /// Whether the frame or the state changed this tick.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+
+    /// Changes the base state. A one-shot clip playing finishes first.
+    pub fn set_state(&mut self, state: &str) {
+        if self.base == state {
+            return;
+        }
+        let via = self
+            .transitions
+            .iter()
+            .find(|transition| transition.from == self.base && transition.to == state)
+            .map(|transition| transition.via.clone());
+        self.base = state.to_string();
+        match via {
+            Some(via) => self.play_once(via),
+            None if self.one_shot.is_none() => self.restart(),
+            None => {}
+        }
+    }
+
+    /// Plays a clip once, then goes back to the base state. It replaces the one-shot clip
+    /// playing, if any.
+    pub fn play_once(&mut self, state: impl Into<String>) {
+        self.one_shot = Some(state.into());
+        self.restart();
+    }
+
+    /// Changes the direction, keeping the frame. [`Direction::Nothing`] keeps the current one.
+    pub fn set_direction(&mut self, direction: Direction) {
+        if direction != Direction::Nothing && self.direction != direction {
+            self.direction = direction;
+            self.changed = true;
+        }
+    }
+
+    /// The clip playing, and whether to flip it.
+    pub fn clip<'a>(&self, set: &'a AnimationSet) -> Option<(&'a AnimationClip, bool)> {
+        set.clip(self.state(), self.direction)
+    }
+
+    fn restart(&mut self) {
+        self.frame = 0;
+        // The duration of the first frame is only known once the set is.
+        self.timer = Timer::new(default(), TimerMode::Once);
+        self.changed = true;
+        self.entered = true;
+    }
+
+    /// Advances the clip playing.
+    pub fn tick(&mut self, set: &AnimationSet, delta: Duration) {
+        self.changed = false;
+        self.entered = false;
+        self.finished = None;
+        let Some((clip, _)) = self.clip(set) else {
+            if self.one_shot.is_some() {
+                self.finished = self.one_shot.take();
+                self.restart();
+            }
+            return;
+        };
+        if self.timer.duration().is_zero() {
+            self.timer = Timer::new(clip.duration(self.frame), TimerMode::Once);
+        }
+        self.timer.tick(delta);
+        if !self.timer.is_finished() {
+            return;
+        }
+        match clip.next_frame(self.frame) {
+            Some(frame) => {
+                self.frame = frame;
+                self.changed = true;
+                self.entered = true;
+                self.timer = Timer::new(clip.duration(frame), TimerMode::Once);
+            }
+            None if self.one_shot.is_some() => {
+                self.finished = self.one_shot.take();
+                self.restart();
+            }
+            // A base state that doesn't loop holds its last frame.
+            None => {}
+        }
+    }
+}
+
+/// Sent on the entity of an [`Animator`] when a frame with an event in its clip shows.
+#[derive(EntityEvent, Debug, Clone)]
+pub struct AnimationEvent {
+    pub entity: Entity,
+    /// The name of the event in the clip.
+    pub name: String,
+}
+
+/// Sent on the entity of an [`Animator`] when a one-shot clip ends.
+#[derive(EntityEvent, Debug, Clone)]
+pub struct AnimationFinished {
+    pub entity: Entity,
+    /// The one-shot clip that ended.
+    pub state: String,
+}
+
+fn tick_animators(
+    time: Res<Time>,
+    sets: Res<Assets<AnimationSet>>,
+    mut animator_query: Query<&mut Animator>,
+) {
+    for mut animator in &mut animator_query {
+        let Some(set) = sets.get(&animator.set) else {
+            continue;
+        };
+        animator.tick(set, time.delta());
+    }
+}
+
+/// Update the texture atlas and flip to reflect changes in the animation.
+fn update_animator_atlas(
+    sets: Res<Assets<AnimationSet>>,
+    mut animator_query: Query<(&Animator, &mut Sprite)>,
+) {
+    for (animator, mut sprite) in &mut animator_query {
+        if !animator.changed() {
+            continue;
+        }
+        let Some((clip, flip_x)) = sets.get(&animator.set).and_then(|set| animator.clip(set))
+        else {
+            continue;
+        };
+        sprite.flip_x = flip_x;
+        if let Some(atlas) = sprite.texture_atlas.as_mut() {
+            atlas.index = clip.atlas_index(animator.frame);
+        }
+    }
+}
+
+fn trigger_animation_events(
+    mut commands: Commands,
+    sets: Res<Assets<AnimationSet>>,
+    animator_query: Query<(Entity, &Animator)>,
+) {
+    for (entity, animator) in &animator_query {
+        if let Some(state) = animator.finished.clone() {
+            commands.trigger(AnimationFinished { entity, state });
+        }
+        if !animator.entered {
+            continue;
+        }
+        let Some((clip, _)) = sets.get(&animator.set).and_then(|set| animator.clip(set)) else {
+            continue;
+        };
+        if let Some(name) = clip.events.get(&animator.frame) {
+            commands.trigger(AnimationEvent {
+                entity,
+                name: name.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::system::RunSystemOnce, platform::collections::HashMap};
+
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(100);
+
+    fn clip(frames: Vec<usize>, looping: bool) -> HashMap<Direction, AnimationClip> {
+        let clip = AnimationClip {
+            frames,
+            frame_duration: FRAME.as_secs_f32(),
+            frame_durations: Vec::new(),
+            looping,
+            events: [(1, "step".to_string())].into_iter().collect(),
+        };
+        [(Direction::Nothing, clip)].into_iter().collect()
+    }
+
+    fn set() -> AnimationSet {
+        AnimationSet {
+            image: default(),
+            layout: default(),
+            frame_size: UVec2::splat(16),
+            clips: [
+                ("idle".to_string(), clip(vec![0, 1], true)),
+                ("walk".to_string(), clip(vec![2, 3, 4, 5], true)),
+                ("start".to_string(), clip(vec![6], false)),
+                ("attack".to_string(), clip(vec![7, 8], false)),
+            ]
+            .into_iter()
+            .collect(),
+            mirror_x: default(),
+            slices: default(),
+        }
+    }
+
+    /// Ticks until the frame changes, or at most a frame's time.
+    fn next_frame(animator: &mut Animator, set: &AnimationSet) {
+        // The first tick of a clip only starts its timer.
+        animator.tick(set, Duration::ZERO);
+        animator.tick(set, FRAME);
+    }
+
+    #[test]
+    fn one_shot_clips_return_to_the_base_state() {
+        let set = set();
+        let mut animator = Animator::new(default(), "idle");
+        animator.play_once("attack");
+
+        next_frame(&mut animator, &set);
+        assert_eq!((animator.state(), animator.frame), ("attack", 1));
+        // The base state can change under it.
+        animator.set_state("walk");
+        assert_eq!(animator.state(), "attack");
+
+        animator.tick(&set, FRAME);
+        assert_eq!(animator.finished.as_deref(), Some("attack"));
+        assert_eq!((animator.state(), animator.frame), ("walk", 0));
+        assert!(animator.changed());
+    }
+
+    #[test]
+    fn one_shot_clips_the_set_lacks_end_at_once() {
+        let set = set();
+        let mut animator = Animator::new(default(), "idle");
+        animator.play_once("death");
+
+        animator.tick(&set, Duration::ZERO);
+        assert_eq!(animator.finished.as_deref(), Some("death"));
+        assert_eq!(animator.state(), "idle");
+    }
+
+    #[test]
+    fn transitions_play_between_their_states() {
+        let set = set();
+        let mut animator =
+            Animator::new(default(), "idle").with_transition("idle", "walk", "start");
+
+        animator.set_state("walk");
+        assert_eq!(animator.state(), "start");
+        next_frame(&mut animator, &set);
+        assert_eq!(animator.finished.as_deref(), Some("start"));
+        assert_eq!(animator.state(), "walk");
+
+        // Only from `idle` to `walk`.
+        animator.set_state("idle");
+        assert_eq!((animator.state(), animator.frame), ("idle", 0));
+    }
+
+    #[test]
+    fn base_states_that_do_not_loop_hold_their_last_frame() {
+        let set = set();
+        let mut animator = Animator::new(default(), "attack");
+
+        next_frame(&mut animator, &set);
+        animator.tick(&set, FRAME * 3);
+        assert_eq!((animator.state(), animator.frame), ("attack", 1));
+        assert_eq!(animator.finished, None);
+        assert!(!animator.changed());
+    }
+
+    #[derive(Resource, Default)]
+    struct Sent(Vec<String>);
+
+    #[test]
+    fn frame_events_are_sent_once_per_frame_shown() {
+        let mut world = World::new();
+        world.init_resource::<Sent>();
+        world.add_observer(|event: On<AnimationEvent>, mut events: ResMut<Sent>| {
+            events.0.push(event.name.clone());
+        });
+        world.add_observer(
+            |finished: On<AnimationFinished>, mut events: ResMut<Sent>| {
+                events.0.push(format!("{} finished", finished.state));
+            },
+        );
+        let mut sets = Assets::<AnimationSet>::default();
+        let handle = sets.add(set());
+        world.insert_resource(sets);
+        let entity = world.spawn(Animator::new(handle.clone(), "walk")).id();
+
+        let tick = |world: &mut World, delta| {
+            world.resource_scope(|world, sets: Mut<Assets<AnimationSet>>| {
+                let set = sets.get(&handle).unwrap();
+                world.get_mut::<Animator>(entity).unwrap().tick(set, delta);
+            });
+            world.run_system_once(trigger_animation_events).unwrap();
+        };
+        // Twice around the walk cycle, in steps shorter than a frame.
+        for _ in 0..(8 * 4) {
+            tick(&mut world, FRAME / 4);
+        }
+        assert_eq!(world.resource::<Sent>().0, ["step", "step"]);
+
+        world
+            .get_mut::<Animator>(entity)
+            .unwrap()
+            .play_once("attack");
+        for _ in 0..2 {
+            tick(&mut world, FRAME);
+        }
+        assert_eq!(
+            world.resource::<Sent>().0,
+            ["step", "step", "step", "attack finished"]
+        );
+    }
+}
@@ -0,0 +1,420 @@
+//! Load Aseprite sprite sheet exports as [`AnimationSet`]s, so that animations can be changed in
+//! Aseprite without touching the code.
+//!
+//! Export with `File > Export Sprite Sheet`, with the JSON data in either the "Hash" or the
+//! "Array" format, frame tags and slices included and trimming off, and name the JSON file
+//! `<name>.aseprite.json`. `.aseprite` files themselves are not read.
+//!
+//! Tags are named after the state and, optionally, the direction: `idle`, `walk-down`,
+//! `walk-up-right`... The states are the ones an [`Animator`] plays; characters moving with a
+//! `MovementController` use `idle` and `walk`. Left directions without a tag of their own use the
+//! right ones flipped.
+//!
+//! [`Animator`]: crate::utils::animator::Animator
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, ParseAssetPathError, io::Reader},
+    platform::collections::HashMap,
+    prelude::*,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::utils::animation_set::{AnimationClip, AnimationSet, AnimationSlice, Direction};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_asset_loader(AsepriteLoader);
+}
+
+#[derive(Debug, Deserialize)]
+struct AsepriteFile {
+    frames: AsepriteFrames,
+    meta: AsepriteMeta,
+}
+
+/// The "Hash" format keys the frames by file name, the "Array" format lists them.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum AsepriteFrames {
+    Array(Vec<AsepriteFrame>),
+    Hash(serde_json::Map<String, serde_json::Value>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteFrame {
+    frame: AsepriteRect,
+    source_size: AsepriteSize,
+    /// In milliseconds.
+    duration: u32,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct AsepriteRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+impl From<AsepriteRect> for URect {
+    fn from(rect: AsepriteRect) -> Self {
+        URect::new(rect.x, rect.y, rect.x + rect.w, rect.y + rect.h)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AsepriteSize {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteMeta {
+    image: String,
+    size: AsepriteSize,
+    #[serde(default)]
+    frame_tags: Vec<AsepriteTag>,
+    #[serde(default)]
+    slices: Vec<AsepriteSlice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AsepriteTag {
+    name: String,
+    from: usize,
+    to: usize,
+    /// `forward`, `reverse`, `pingpong` or `pingpong_reverse`.
+    #[serde(default)]
+    direction: String,
+    /// How many times the tag plays, if it doesn't loop forever.
+    #[serde(default)]
+    repeat: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AsepriteSlice {
+    name: String,
+    keys: Vec<AsepriteSliceKey>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AsepriteSliceKey {
+    frame: usize,
+    bounds: AsepriteRect,
+}
+
+#[derive(Debug, Error)]
+pub enum AsepriteLoaderError {
+    #[error("Could not load Aseprite export: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse Aseprite export: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid image path in Aseprite export: {0}")]
+    ImagePath(#[from] ParseAssetPathError),
+    #[error("Tag {0:?} is not named `state` or `state-direction`")]
+    TagName(String),
+}
+
+#[derive(TypePath)]
+pub struct AsepriteLoader;
+
+impl AssetLoader for AsepriteLoader {
+    type Asset = AnimationSet;
+    type Settings = ();
+    type Error = AsepriteLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let sheet = parse(&bytes)?;
+
+        let image_path = load_context.path().resolve_embed(&sheet.image)?;
+        Ok(AnimationSet {
+            image: load_context.load(image_path),
+            layout: load_context.add_labeled_asset("layout".to_string(), sheet.layout),
+            frame_size: sheet.frame_size,
+            clips: sheet.clips,
+            mirror_x: [
+                (Direction::Left, Direction::Right),
+                (Direction::UpLeft, Direction::UpRight),
+                (Direction::DownLeft, Direction::DownRight),
+            ]
+            .into_iter()
+            .collect(),
+            slices: sheet.slices,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["aseprite.json"]
+    }
+}
+
+/// What an Aseprite export describes, before loading its image.
+struct AsepriteSheet {
+    /// Path of the image, relative to the export.
+    image: String,
+    layout: TextureAtlasLayout,
+    frame_size: UVec2,
+    clips: HashMap<String, HashMap<Direction, AnimationClip>>,
+    slices: HashMap<String, Vec<AnimationSlice>>,
+}
+
+fn parse(bytes: &[u8]) -> Result<AsepriteSheet, AsepriteLoaderError> {
+    let file: AsepriteFile = serde_json::from_slice(bytes)?;
+    let frames = match file.frames {
+        AsepriteFrames::Array(frames) => frames,
+        AsepriteFrames::Hash(frames) => frames
+            .into_iter()
+            .map(|(_, frame)| serde_json::from_value(frame))
+            .collect::<Result<_, _>>()?,
+    };
+
+    // Atlas indices follow the frame numbers of Aseprite.
+    let mut layout = TextureAtlasLayout::new_empty(UVec2::new(file.meta.size.w, file.meta.size.h));
+    for frame in &frames {
+        layout.add_texture(frame.frame.into());
+    }
+    let frame_size = frames.first().map_or(UVec2::ZERO, |frame| {
+        UVec2::new(frame.source_size.w, frame.source_size.h)
+    });
+
+    let mut clips = HashMap::<String, HashMap<Direction, AnimationClip>>::default();
+    for tag in &file.meta.frame_tags {
+        let (state, direction) = parse_tag_name(&tag.name)
+            .ok_or_else(|| AsepriteLoaderError::TagName(tag.name.clone()))?;
+        let indices = tag_frames(tag);
+        let frame_durations = indices
+            .iter()
+            .map(|&i| {
+                frames
+                    .get(i)
+                    .map_or(0.0, |frame| frame.duration as f32 / 1000.0)
+            })
+            .collect();
+        clips.entry(state).or_default().insert(
+            direction,
+            AnimationClip {
+                frames: indices,
+                frame_duration: 0.1,
+                frame_durations,
+                looping: tag.repeat.is_none(),
+                events: default(),
+            },
+        );
+    }
+
+    // Slice keys start at an Aseprite frame number, which is also their atlas index here.
+    let slices = file
+        .meta
+        .slices
+        .into_iter()
+        .map(|slice| {
+            let keys = slice
+                .keys
+                .into_iter()
+                .map(|key| AnimationSlice {
+                    frame: key.frame,
+                    bounds: key.bounds.into(),
+                })
+                .collect();
+            (slice.name, keys)
+        })
+        .collect();
+
+    Ok(AsepriteSheet {
+        image: file.meta.image,
+        layout,
+        frame_size,
+        clips,
+        slices,
+    })
+}
+
+/// The frames of a tag, in the order they play.
+fn tag_frames(tag: &AsepriteTag) -> Vec<usize> {
+    let forward: Vec<usize> = (tag.from..=tag.to).collect();
+    let reverse: Vec<usize> = forward.iter().rev().copied().collect();
+    // Ping-pong doesn't repeat the frames at either end.
+    let bounce = |there: &[usize], back: &[usize]| {
+        let back = back
+            .get(1..back.len().saturating_sub(1))
+            .unwrap_or_default();
+        there.iter().chain(back).copied().collect()
+    };
+    match tag.direction.as_str() {
+        "reverse" => reverse,
+        "pingpong" => bounce(&forward, &reverse),
+        "pingpong_reverse" => bounce(&reverse, &forward),
+        _ => forward,
+    }
+}
+
+/// Splits `walk-up-right` into the state `walk` and [`Direction::UpRight`]. A tag without a
+/// direction gives [`Direction::Nothing`].
+fn parse_tag_name(name: &str) -> Option<(String, Direction)> {
+    let Some((state, direction)) = name.split_once('-') else {
+        return Some((name.to_string(), Direction::Nothing));
+    };
+    let direction = match direction
+        .replace(['-', '_', ' '], "")
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "up" => Direction::Up,
+        "down" => Direction::Down,
+        "left" => Direction::Left,
+        "right" => Direction::Right,
+        "upright" => Direction::UpRight,
+        "upleft" => Direction::UpLeft,
+        "downright" => Direction::DownRight,
+        "downleft" => Direction::DownLeft,
+        _ => return None,
+    };
+    Some((state.to_string(), direction))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// Four 16x16 frames side by side, exported in the "Hash" format.
+    const SHEET: &str = r#"{
+        "frames": {
+            "hero 0.aseprite": { "frame": { "x": 0, "y": 0, "w": 16, "h": 16 }, "sourceSize": { "w": 16, "h": 16 }, "duration": 100 },
+            "hero 1.aseprite": { "frame": { "x": 16, "y": 0, "w": 16, "h": 16 }, "sourceSize": { "w": 16, "h": 16 }, "duration": 150 },
+            "hero 2.aseprite": { "frame": { "x": 32, "y": 0, "w": 16, "h": 16 }, "sourceSize": { "w": 16, "h": 16 }, "duration": 200 },
+            "hero 3.aseprite": { "frame": { "x": 48, "y": 0, "w": 16, "h": 16 }, "sourceSize": { "w": 16, "h": 16 }, "duration": 250 }
+        },
+        "meta": {
+            "image": "hero.png",
+            "size": { "w": 64, "h": 16 },
+            "frameTags": [
+                { "name": "idle", "from": 0, "to": 1, "direction": "forward" },
+                { "name": "walk-down", "from": 2, "to": 3, "direction": "forward" },
+                { "name": "walk-up-right", "from": 1, "to": 3, "direction": "reverse" },
+                { "name": "attack", "from": 0, "to": 3, "direction": "pingpong", "repeat": "1" }
+            ],
+            "slices": [
+                { "name": "hitbox", "keys": [
+                    { "frame": 0, "bounds": { "x": 4, "y": 2, "w": 8, "h": 14 } },
+                    { "frame": 2, "bounds": { "x": 6, "y": 4, "w": 6, "h": 12 } }
+                ] }
+            ]
+        }
+    }"#;
+
+    fn clip<'a>(sheet: &'a AsepriteSheet, state: &str, direction: Direction) -> &'a AnimationClip {
+        &sheet.clips[state][&direction]
+    }
+
+    #[test]
+    fn tags_become_states_and_directions() {
+        let sheet = parse(SHEET.as_bytes()).unwrap();
+
+        assert_eq!(sheet.image, "hero.png");
+        assert_eq!(sheet.frame_size, UVec2::new(16, 16));
+        assert_eq!(sheet.layout.size, UVec2::new(64, 16));
+        assert_eq!(sheet.layout.textures[2], URect::new(32, 0, 48, 16));
+
+        let mut states: Vec<_> = sheet.clips.keys().map(String::as_str).collect();
+        states.sort();
+        assert_eq!(states, ["attack", "idle", "walk"]);
+        assert_eq!(clip(&sheet, "idle", Direction::Nothing).frames, [0, 1]);
+        assert_eq!(clip(&sheet, "walk", Direction::Down).frames, [2, 3]);
+        assert_eq!(clip(&sheet, "walk", Direction::UpRight).frames, [3, 2, 1]);
+        // Ping-pong plays the frames at either end once.
+        let attack = clip(&sheet, "attack", Direction::Nothing);
+        assert_eq!(attack.frames, [0, 1, 2, 3, 2, 1]);
+        // Tags repeating a set number of times play once.
+        assert!(!attack.looping);
+        assert!(clip(&sheet, "idle", Direction::Nothing).looping);
+    }
+
+    #[test]
+    fn frames_keep_their_own_duration() {
+        let sheet = parse(SHEET.as_bytes()).unwrap();
+
+        let attack = clip(&sheet, "attack", Direction::Nothing);
+        assert_eq!(attack.frame_durations, [0.1, 0.15, 0.2, 0.25, 0.2, 0.15]);
+        assert_eq!(attack.duration(3), Duration::from_millis(250));
+        assert_eq!(
+            clip(&sheet, "walk", Direction::UpRight).frame_durations,
+            [0.25, 0.2, 0.15]
+        );
+    }
+
+    #[test]
+    fn slice_keys_start_at_atlas_indices() {
+        let sheet = parse(SHEET.as_bytes()).unwrap();
+
+        let keys = &sheet.slices["hitbox"];
+        assert_eq!(
+            keys.iter()
+                .map(|key| (key.frame, key.bounds))
+                .collect::<Vec<_>>(),
+            [(0, URect::new(4, 2, 12, 16)), (2, URect::new(6, 4, 12, 16))]
+        );
+        let set = AnimationSet {
+            image: default(),
+            layout: default(),
+            frame_size: sheet.frame_size,
+            clips: sheet.clips,
+            mirror_x: default(),
+            slices: sheet.slices,
+        };
+        // The second key holds from atlas index 2 on, whichever clip shows it.
+        assert_eq!(
+            set.slice("hitbox", 1, false),
+            Some(Rect::new(-4.0, -8.0, 4.0, 6.0))
+        );
+        assert_eq!(
+            set.slice("hitbox", 3, false),
+            Some(Rect::new(-2.0, -8.0, 4.0, 4.0))
+        );
+        assert_eq!(
+            set.slice("hitbox", 3, true),
+            Some(Rect::new(-4.0, -8.0, 2.0, 4.0))
+        );
+        assert_eq!(set.slice("hurtbox", 0, false), None);
+    }
+
+    #[test]
+    fn array_exports_load_the_same() {
+        let value: serde_json::Value = serde_json::from_str(SHEET).unwrap();
+        let mut array = value.clone();
+        array["frames"] = serde_json::Value::Array(
+            value["frames"]
+                .as_object()
+                .unwrap()
+                .values()
+                .cloned()
+                .collect(),
+        );
+        let hash = parse(SHEET.as_bytes()).unwrap();
+        let array = parse(array.to_string().as_bytes()).unwrap();
+        assert_eq!(array.layout.textures, hash.layout.textures);
+        assert_eq!(
+            clip(&array, "attack", Direction::Nothing).frame_durations,
+            clip(&hash, "attack", Direction::Nothing).frame_durations
+        );
+    }
+
+    #[test]
+    fn tags_with_unknown_directions_are_errors() {
+        let sheet = SHEET.replace("walk-down", "walk-sideways");
+        assert!(matches!(
+            parse(sheet.as_bytes()),
+            Err(AsepriteLoaderError::TagName(name)) if name == "walk-sideways"
+        ));
+    }
+}
@@ -1,46 +1,992 @@
-use bevy::{camera::*, prelude::*};
+use avian2d::prelude::*;
+use bevy::{
+    camera::{visibility::RenderLayers, *},
+    image::BevyDefault as _,
+    input::mouse::AccumulatedMouseScroll,
+    prelude::*,
+    render::render_resource::TextureFormat,
+    window::{PrimaryWindow, WindowResized},
+};
+use tiled::PropertyValue;
 
-use crate::demo::player::Player;
+use crate::{
+    PausableSystems,
+    input::{Action, ActionState},
+    screens::Screen,
+    utils::tiled::{TiledMap, TiledMapHandle, TiledMapInfo, TiledMapSpawned},
+};
 
 pub const FOLLOW_CAMERA_TRESHOLD: f32 = 100.0; // Determine based on the character speed
 pub const FOLLOW_CAMERA_MAX_SPEED: f32 = 1000.0;
 pub const FOLLOW_CAMERA_BASE_SPEED: f32 = 4.5;
+/// How much one step of zoom input multiplies the [`CameraZoom`] target by.
+pub const ZOOM_STEP: f32 = 1.1;
+
+/// The custom map property naming the [`FollowCamera`] preset to use on that map.
+pub const CAMERA_PROFILE_PROPERTY: &str = "camera_profile";
 
 pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CameraShakeSettings>();
+    app.init_resource::<PixelPerfect>();
     app.add_systems(Startup, spawn_camera);
-    app.add_systems(Update, update_camera);
+    // The shake and the pixel snapping are taken off before following and put back after, so
+    // they don't fight with it.
+    app.add_systems(
+        Update,
+        (
+            unsnap_camera,
+            remove_camera_shake,
+            update_camera_zoom,
+            update_camera,
+            apply_camera_shake,
+            snap_camera,
+        )
+            .chain(),
+    );
+    app.add_systems(
+        Update,
+        (
+            respawn_camera
+                .run_if(resource_changed::<PixelPerfect>.and(not(resource_added::<PixelPerfect>))),
+            fit_canvas.run_if(on_message::<WindowResized>.or(resource_changed::<PixelPerfect>)),
+        )
+            .chain(),
+    );
+    app.add_systems(
+        Update,
+        record_zoom_input
+            .run_if(in_state(Screen::Gameplay))
+            .in_set(PausableSystems),
+    );
+    app.add_observer(apply_map_camera_profile);
+    app.add_observer(zoom_camera);
+    app.add_observer(focus_camera);
+    app.add_observer(release_camera_focus);
+    app.add_observer(shake_camera);
+    app.add_observer(kick_camera);
+}
+
+fn spawn_camera(
+    mut commands: Commands,
+    pixel_perfect: Res<PixelPerfect>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    spawn_camera_rig(
+        &mut commands,
+        &pixel_perfect,
+        &mut images,
+        CameraState::default(),
+    );
+}
+
+/// What the game camera keeps when the rig is respawned.
+#[derive(Debug, Clone, Default)]
+struct CameraState {
+    position: Vec2,
+    follow: FollowCamera,
+    zoom: CameraZoom,
+    focus: CameraFocus,
+    shake: CameraShake,
 }
 
-fn spawn_camera(mut commands: Commands) {
+/// Swaps the cameras when [`PixelPerfect`] is turned on or off, keeping where they look.
+fn respawn_camera(
+    mut commands: Commands,
+    pixel_perfect: Res<PixelPerfect>,
+    mut images: ResMut<Assets<Image>>,
+    rig_query: Query<Entity, With<CameraRig>>,
+    camera_query: Query<(
+        &Transform,
+        Option<&PixelSnap>,
+        &FollowCamera,
+        &CameraZoom,
+        &CameraFocus,
+        &CameraShake,
+    )>,
+) {
+    let state = camera_query.iter().next().map_or_else(
+        CameraState::default,
+        |(transform, snap, follow, zoom, focus, shake)| CameraState {
+            // Undo the snapping, the new camera snaps on its own if it has to.
+            position: transform.translation.truncate() + snap.map_or(Vec2::ZERO, |s| s.remainder),
+            follow: *follow,
+            zoom: *zoom,
+            focus: *focus,
+            shake: shake.clone(),
+        },
+    );
+    for entity in &rig_query {
+        commands.entity(entity).despawn();
+    }
+    spawn_camera_rig(&mut commands, &pixel_perfect, &mut images, state);
+}
+
+/// Spawns the game camera, and in [`PixelPerfect`] mode what upscales it.
+fn spawn_camera_rig(
+    commands: &mut Commands,
+    pixel_perfect: &PixelPerfect,
+    images: &mut Assets<Image>,
+    state: CameraState,
+) {
+    let CameraState {
+        position,
+        follow,
+        zoom,
+        focus,
+        shake,
+    } = state;
+    let transform = Transform::from_translation(position.extend(0.0));
+    if !pixel_perfect.enabled {
+        commands.spawn((
+            Name::new("Camera"),
+            Camera2d,
+            Projection::Orthographic(OrthographicProjection {
+                scaling_mode: ScalingMode::WindowSize,
+                scale: zoom.target.clamp(zoom.min, zoom.max),
+                ..OrthographicProjection::default_2d()
+            }),
+            transform,
+            follow,
+            zoom,
+            focus,
+            shake,
+            CameraRig,
+        ));
+        return;
+    }
+
+    let resolution = pixel_perfect.resolution;
+    let canvas = images.add(Image::new_target_texture(
+        resolution.x,
+        resolution.y,
+        TextureFormat::bevy_default(),
+        None,
+    ));
+
+    // The game camera sees the world (layer 0) and draws it on the canvas, one texel per world
+    // unit.
     commands.spawn((
         Name::new("Camera"),
         Camera2d,
+        Camera {
+            // Render before the canvas camera.
+            order: -1,
+            ..default()
+        },
+        RenderTarget::Image(canvas.clone().into()),
+        Msaa::Off,
         Projection::Orthographic(OrthographicProjection {
             scaling_mode: ScalingMode::WindowSize,
-            scale: 0.5,
+            scale: 1.0,
             ..OrthographicProjection::default_2d()
         }),
+        transform,
+        follow,
+        // Kept for when pixel-perfect mode is turned off, see `update_camera_zoom`.
+        zoom,
+        focus,
+        shake,
+        PixelSnap::default(),
+        CameraRig,
+    ));
+    // The canvas camera only sees the canvas, and the UI so that it stays sharp.
+    commands.spawn((
+        Name::new("Canvas"),
+        Sprite::from_image(canvas),
+        Canvas,
+        CANVAS_LAYER,
+        CameraRig,
     ));
+    commands.spawn((
+        Name::new("Canvas Camera"),
+        Camera2d,
+        Msaa::Off,
+        CanvasCamera,
+        IsDefaultUiCamera,
+        CANVAS_LAYER,
+        CameraRig,
+    ));
+}
+
+/// The render layer of the upscaled canvas, out of sight of the game camera.
+const CANVAS_LAYER: RenderLayers = RenderLayers::layer(1);
+
+/// Renders the game to a canvas of a fixed virtual resolution, upscaled to the window by an
+/// integer factor and with the camera snapped to whole texels, so pixel art never shimmers.
+///
+/// Toggled from the settings menu. Changing it respawns the cameras.
+#[derive(Resource, Debug, Clone, Copy, Reflect)]
+#[reflect(Resource)]
+pub struct PixelPerfect {
+    pub enabled: bool,
+    /// Size of the canvas, in world units.
+    pub resolution: UVec2,
+}
+
+impl Default for PixelPerfect {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            // What the default camera shows in a 1280x720 window.
+            resolution: UVec2::new(640, 360),
+        }
+    }
+}
+
+/// The cameras and the canvas, which are respawned together when [`PixelPerfect`] changes.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+struct CameraRig;
+
+/// The sprite showing the game canvas in [`PixelPerfect`] mode.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+struct Canvas;
+
+/// The camera upscaling the [`Canvas`] to the window.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+struct CanvasCamera;
+
+/// Rounds the camera to whole texels. The rest is made up for by moving the [`Canvas`], which
+/// keeps the scrolling smooth at the window resolution.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+struct PixelSnap {
+    remainder: Vec2,
+}
+
+/// How the camera follows its [`CameraTarget`]s.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct FollowCamera {
+    /// Half-extents of the rectangle around the camera center in which the target can move
+    /// without the camera following.
+    pub deadzone: Vec2,
+    /// How far ahead of the target to look, in seconds of its [`LinearVelocity`].
+    pub look_ahead: f32,
+    /// Maximum distance of the look-ahead, in world units.
+    pub max_look_ahead: f32,
+    pub smoothing: FollowSmoothing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub enum FollowSmoothing {
+    /// Lerp by `base_speed * dt`, faster the further the target is. Depends on the frame rate.
+    Lerp {
+        base_speed: f32,
+        threshold: f32,
+        max_speed: f32,
+    },
+    /// Exponential decay towards the target, independent of the frame rate.
+    /// See [`StableInterpolate::smooth_nudge`].
+    Exponential { decay_rate: f32 },
+}
+
+impl FollowCamera {
+    /// The original follow camera.
+    pub const CLASSIC: Self = Self {
+        deadzone: Vec2::ZERO,
+        look_ahead: 0.0,
+        max_look_ahead: 0.0,
+        smoothing: FollowSmoothing::Lerp {
+            base_speed: FOLLOW_CAMERA_BASE_SPEED,
+            threshold: FOLLOW_CAMERA_TRESHOLD,
+            max_speed: FOLLOW_CAMERA_MAX_SPEED,
+        },
+    };
+    /// Sticks to the target.
+    pub const TIGHT: Self = Self {
+        deadzone: Vec2::ZERO,
+        look_ahead: 0.0,
+        max_look_ahead: 0.0,
+        smoothing: FollowSmoothing::Exponential { decay_rate: 20.0 },
+    };
+    /// Lets the target move a little and looks where it is heading.
+    pub const SMOOTH: Self = Self {
+        deadzone: Vec2::new(16.0, 12.0),
+        look_ahead: 0.25,
+        max_look_ahead: 48.0,
+        smoothing: FollowSmoothing::Exponential { decay_rate: 6.0 },
+    };
+    /// Slow and far ahead, for open areas.
+    pub const CINEMATIC: Self = Self {
+        deadzone: Vec2::new(48.0, 32.0),
+        look_ahead: 0.5,
+        max_look_ahead: 96.0,
+        smoothing: FollowSmoothing::Exponential { decay_rate: 2.5 },
+    };
+
+    /// The preset with the given name, as used by [`CAMERA_PROFILE_PROPERTY`].
+    pub fn preset(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "classic" => Some(Self::CLASSIC),
+            "tight" => Some(Self::TIGHT),
+            "smooth" => Some(Self::SMOOTH),
+            "cinematic" => Some(Self::CINEMATIC),
+            _ => None,
+        }
+    }
+
+    /// The camera position to move towards, given the target position and velocity.
+    fn goal(&self, camera_pos: Vec2, target_pos: Vec2, target_velocity: Vec2) -> Vec2 {
+        let target =
+            target_pos + (target_velocity * self.look_ahead).clamp_length_max(self.max_look_ahead);
+        let delta = target - camera_pos;
+        let outside = (delta.abs() - self.deadzone).max(Vec2::ZERO);
+        camera_pos + outside * delta.signum()
+    }
+
+    /// Moves the camera towards `goal`.
+    fn smooth(&self, camera_pos: Vec2, goal: Vec2, dt: f32) -> Vec2 {
+        match self.smoothing {
+            FollowSmoothing::Lerp {
+                base_speed,
+                threshold,
+                max_speed,
+            } => {
+                let d = camera_pos.distance(goal);
+
+                // smoothing
+                let factor = (d / threshold).clamp(1.0, max_speed / base_speed);
+                let effective_speed = base_speed * factor;
+
+                camera_pos.lerp(goal, effective_speed * dt)
+            }
+            FollowSmoothing::Exponential { decay_rate } => {
+                let mut pos = camera_pos;
+                pos.smooth_nudge(&goal, decay_rate, dt);
+                pos
+            }
+        }
+    }
+}
+
+impl Default for FollowCamera {
+    fn default() -> Self {
+        Self::CLASSIC
+    }
+}
+
+/// Something the camera follows. With several targets, the camera follows their weighted center
+/// and zooms out to keep them all in view.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct CameraTarget {
+    pub weight: f32,
+}
+
+impl Default for CameraTarget {
+    fn default() -> Self {
+        Self { weight: 1.0 }
+    }
+}
+
+/// The weighted center and velocity of the camera targets.
+fn targets_center<'a>(
+    targets: impl Iterator<Item = (&'a Transform, &'a CameraTarget, Option<&'a LinearVelocity>)>,
+) -> Option<(Vec2, Vec2)> {
+    let mut total_weight = 0.0;
+    let mut center = Vec2::ZERO;
+    let mut velocity = Vec2::ZERO;
+    for (transform, target, target_velocity) in targets {
+        total_weight += target.weight;
+        center += transform.translation.truncate() * target.weight;
+        velocity += target_velocity.map_or(Vec2::ZERO, |v| v.0) * target.weight;
+    }
+    (total_weight > 0.0).then(|| (center / total_weight, velocity / total_weight))
+}
+
+/// A point of interest the camera looks at instead of its targets.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct CameraFocus {
+    pub point: Option<Vec2>,
+    /// Jump to the point on the next update instead of moving there smoothly.
+    pub cut: bool,
+}
+
+/// Points the camera at `point` until [`ReleaseCameraFocus`], e.g. for a scripted scene.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct FocusCamera {
+    pub point: Vec2,
+    /// Jump there instead of moving smoothly.
+    pub cut: bool,
+}
+
+/// Gives the camera back to its [`CameraTarget`]s.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ReleaseCameraFocus;
+
+fn focus_camera(focus: On<FocusCamera>, mut camera_query: Query<&mut CameraFocus>) {
+    for mut camera_focus in &mut camera_query {
+        camera_focus.point = Some(focus.point);
+        camera_focus.cut = focus.cut;
+    }
+}
+
+fn release_camera_focus(_: On<ReleaseCameraFocus>, mut camera_query: Query<&mut CameraFocus>) {
+    for mut camera_focus in &mut camera_query {
+        *camera_focus = CameraFocus::default();
+    }
 }
 
 fn update_camera(
-    player_query: Single<&Transform, With<Player>>,
-    mut camera_query: Query<&mut Transform, (With<Camera2d>, Without<Player>)>,
+    target_query: Query<
+        (&Transform, &CameraTarget, Option<&LinearVelocity>),
+        Without<FollowCamera>,
+    >,
+    mut camera_query: Query<(&mut Transform, &Projection, &FollowCamera, &mut CameraFocus)>,
+    map_query: Query<&TiledMapInfo>,
     time: Res<Time>,
 ) {
-    let player_transform = player_query;
-    if let Ok(mut camera_transform) = camera_query.single_mut() {
+    for (mut camera_transform, projection, follow, mut focus) in &mut camera_query {
         let camera_pos = camera_transform.translation.truncate();
-        let player_pos = player_transform.translation.truncate();
-        let d = camera_pos.distance(player_pos);
 
-        // smoothing
-        let factor = (d / FOLLOW_CAMERA_TRESHOLD)
-            .clamp(1.0, FOLLOW_CAMERA_MAX_SPEED / FOLLOW_CAMERA_BASE_SPEED);
-        let effective_speed = FOLLOW_CAMERA_BASE_SPEED * factor;
+        let mut pos = camera_pos;
+        if let Some(point) = focus.point {
+            if focus.cut {
+                pos = point;
+                focus.cut = false;
+            } else {
+                pos = follow.smooth(camera_pos, point, time.delta_secs());
+            }
+        } else if let Some((target_pos, target_velocity)) = targets_center(target_query.iter()) {
+            let goal = follow.goal(camera_pos, target_pos, target_velocity);
+            pos = follow.smooth(camera_pos, goal, time.delta_secs());
+        }
 
-        let pos: Vec2 = camera_pos.lerp(player_pos, effective_speed * time.delta_secs());
+        if let (Some(map_info), Projection::Orthographic(ortho)) =
+            (map_query.iter().next(), projection)
+        {
+            pos = clamp_to_bounds(pos, ortho.area, map_info.bounds);
+        }
         camera_transform.translation.x = pos.x;
         camera_transform.translation.y = pos.y;
     }
 }
+
+/// Keeps the view inside `bounds`. `area` is the visible area relative to the camera, which
+/// already accounts for the window size and the projection scale.
+/// On the axes where the bounds fit entirely inside the view, the view is centered on them.
+fn clamp_to_bounds(pos: Vec2, area: Rect, bounds: Rect) -> Vec2 {
+    let min = bounds.min - area.min;
+    let max = bounds.max - area.max;
+    let centered = bounds.center() - area.center();
+    Vec2::new(
+        if min.x <= max.x {
+            pos.x.clamp(min.x, max.x)
+        } else {
+            centered.x
+        },
+        if min.y <= max.y {
+            pos.y.clamp(min.y, max.y)
+        } else {
+            centered.y
+        },
+    )
+}
+
+/// Smooth zoom of the camera's [`OrthographicProjection::scale`].
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct CameraZoom {
+    /// The requested scale. Lower is closer.
+    pub target: f32,
+    pub min: f32,
+    pub max: f32,
+    /// See [`StableInterpolate::smooth_nudge`].
+    pub decay_rate: f32,
+    /// Room kept around the [`CameraTarget`]s when zooming out to frame them, in world units.
+    pub framing_margin: f32,
+}
+
+impl Default for CameraZoom {
+    fn default() -> Self {
+        Self {
+            target: 0.5,
+            min: 0.25,
+            max: 1.0,
+            decay_rate: 8.0,
+            framing_margin: 48.0,
+        }
+    }
+}
+
+/// Sets the [`CameraZoom`] target, within its limits.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ZoomCamera {
+    pub scale: f32,
+}
+
+fn zoom_camera(zoom: On<ZoomCamera>, mut camera_query: Query<&mut CameraZoom>) {
+    for mut camera_zoom in &mut camera_query {
+        camera_zoom.target = zoom.scale.clamp(camera_zoom.min, camera_zoom.max);
+    }
+}
+
+/// Zooms with the mouse wheel and the zoom actions.
+fn record_zoom_input(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    scroll: Res<AccumulatedMouseScroll>,
+    camera_query: Query<&CameraZoom>,
+) {
+    let mut steps = 0.0;
+    // Scrolling up zooms in.
+    if scroll.delta.y > 0.0 {
+        steps -= 1.0;
+    } else if scroll.delta.y < 0.0 {
+        steps += 1.0;
+    }
+    if actions.just_pressed(Action::ZoomOut) {
+        steps += 1.0;
+    }
+    if actions.just_pressed(Action::ZoomIn) {
+        steps -= 1.0;
+    }
+    if steps == 0.0 {
+        return;
+    }
+    if let Some(camera_zoom) = camera_query.iter().next() {
+        commands.trigger(ZoomCamera {
+            scale: camera_zoom.target * ZOOM_STEP.powf(steps),
+        });
+    }
+}
+
+/// Cameras snapped to whole texels are left at a scale of 1, as any other one would stretch the
+/// texels unevenly again.
+fn update_camera_zoom(
+    target_query: Query<(&Transform, &CameraTarget, Option<&LinearVelocity>), Without<CameraZoom>>,
+    mut camera_query: Query<(&mut Projection, &CameraZoom, &CameraFocus), Without<PixelSnap>>,
+    time: Res<Time>,
+) {
+    for (mut projection, zoom, focus) in &mut camera_query {
+        let Projection::Orthographic(ortho) = &mut *projection else {
+            continue;
+        };
+        let mut goal = zoom.target;
+
+        // Zoom out until every target fits. The camera is centered on their weighted center, so
+        // the view has to reach the furthest target from there on both sides.
+        let unscaled_size = ortho.area.size() / ortho.scale;
+        if focus.point.is_none()
+            && unscaled_size.min_element() > 0.0
+            && let Some((center, _)) = targets_center(target_query.iter())
+        {
+            let half_size = target_query
+                .iter()
+                .filter(|(_, target, _)| target.weight > 0.0)
+                .fold(Vec2::ZERO, |half_size, (transform, _, _)| {
+                    half_size.max((transform.translation.truncate() - center).abs())
+                });
+            let size = 2.0 * (half_size + Vec2::splat(zoom.framing_margin));
+            goal = goal.max((size / unscaled_size).max_element());
+        }
+
+        let goal = goal.clamp(zoom.min, zoom.max);
+        let scale = ortho.scale;
+        ortho
+            .scale
+            .smooth_nudge(&goal, zoom.decay_rate, time.delta_secs());
+        // The area follows the scale only in `PostUpdate`. Scale it now, so that `update_camera`
+        // clamps to the bounds with this frame's zoom rather than the last one.
+        if scale > 0.0 {
+            let ratio = ortho.scale / scale;
+            ortho.area = Rect {
+                min: ortho.area.min * ratio,
+                max: ortho.area.max * ratio,
+            };
+        }
+    }
+}
+
+/// Maps can pick how the camera feels through the [`CAMERA_PROFILE_PROPERTY`] property.
+fn apply_map_camera_profile(
+    spawned: On<TiledMapSpawned>,
+    maps: Res<Assets<TiledMap>>,
+    map_query: Query<&TiledMapHandle>,
+    mut camera_query: Query<&mut FollowCamera>,
+) {
+    let Some(tiled_map) = map_query
+        .get(spawned.entity)
+        .ok()
+        .and_then(|handle| maps.get(&handle.0))
+    else {
+        return;
+    };
+    let follow = match tiled_map.map.properties.get(CAMERA_PROFILE_PROPERTY) {
+        Some(PropertyValue::StringValue(name)) => FollowCamera::preset(name).unwrap_or_else(|| {
+            warn!("Unknown camera profile {name:?}, using the default one.");
+            FollowCamera::default()
+        }),
+        _ => FollowCamera::default(),
+    };
+    for mut camera_follow in &mut camera_query {
+        *camera_follow = follow;
+    }
+}
+
+/// Accessibility setting for [`CameraShake`].
+#[derive(Resource, Debug, Clone, Copy, Reflect)]
+#[reflect(Resource)]
+pub struct CameraShakeSettings {
+    pub enabled: bool,
+}
+
+impl Default for CameraShakeSettings {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Adds trauma to the camera shake, e.g. `commands.trigger(ShakeCamera { trauma: 0.4 })`.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ShakeCamera {
+    /// Between 0 and 1. The trauma of the camera is capped at 1.
+    pub trauma: f32,
+}
+
+/// Kicks the camera in a direction, e.g. the one of a hit. The kick springs back on its own.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct KickCamera {
+    /// Direction and length of the kick, in world units.
+    pub impulse: Vec2,
+}
+
+/// Trauma-based camera shake. The offset and rotation follow noise scaled by the square of the
+/// trauma, which decays over time.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct CameraShake {
+    /// Between 0 and 1.
+    pub trauma: f32,
+    /// Trauma lost per second.
+    pub decay: f32,
+    /// Offset at full trauma, in world units.
+    pub max_offset: Vec2,
+    /// Rotation at full trauma, in radians.
+    pub max_angle: f32,
+    /// How fast the noise changes.
+    pub frequency: f32,
+    /// How fast kicks spring back, see [`StableInterpolate::smooth_nudge`].
+    pub kick_recovery: f32,
+    kick: Vec2,
+    time: f32,
+    applied_offset: Vec2,
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self {
+            trauma: 0.0,
+            decay: 1.5,
+            max_offset: Vec2::new(12.0, 8.0),
+            max_angle: 0.05,
+            frequency: 15.0,
+            kick_recovery: 12.0,
+            kick: Vec2::ZERO,
+            time: 0.0,
+            applied_offset: Vec2::ZERO,
+        }
+    }
+}
+
+fn shake_camera(
+    shake: On<ShakeCamera>,
+    settings: Res<CameraShakeSettings>,
+    mut shake_query: Query<&mut CameraShake>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    for mut camera_shake in &mut shake_query {
+        camera_shake.trauma = (camera_shake.trauma + shake.trauma).clamp(0.0, 1.0);
+    }
+}
+
+fn kick_camera(
+    kick: On<KickCamera>,
+    settings: Res<CameraShakeSettings>,
+    mut shake_query: Query<&mut CameraShake>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    for mut camera_shake in &mut shake_query {
+        camera_shake.kick += kick.impulse;
+    }
+}
+
+fn remove_camera_shake(mut camera_query: Query<(&mut Transform, &mut CameraShake)>) {
+    for (mut transform, mut camera_shake) in &mut camera_query {
+        transform.translation -= camera_shake.applied_offset.extend(0.0);
+        transform.rotation = Quat::IDENTITY;
+        camera_shake.applied_offset = Vec2::ZERO;
+    }
+}
+
+fn apply_camera_shake(
+    time: Res<Time>,
+    settings: Res<CameraShakeSettings>,
+    mut camera_query: Query<(&mut Transform, &mut CameraShake)>,
+) {
+    for (mut transform, mut camera_shake) in &mut camera_query {
+        let dt = time.delta_secs();
+        camera_shake.time += dt * camera_shake.frequency;
+        camera_shake.trauma = (camera_shake.trauma - camera_shake.decay * dt).max(0.0);
+        let recovery = camera_shake.kick_recovery;
+        camera_shake.kick.smooth_nudge(&Vec2::ZERO, recovery, dt);
+        if !settings.enabled {
+            camera_shake.trauma = 0.0;
+            camera_shake.kick = Vec2::ZERO;
+            continue;
+        }
+
+        let shake = camera_shake.trauma * camera_shake.trauma;
+        let t = camera_shake.time;
+        let offset = camera_shake.kick
+            + shake * camera_shake.max_offset * Vec2::new(noise(0, t), noise(1, t));
+        let angle = shake * camera_shake.max_angle * noise(2, t);
+
+        transform.translation += offset.extend(0.0);
+        transform.rotation = Quat::from_rotation_z(angle);
+        camera_shake.applied_offset = offset;
+    }
+}
+
+fn unsnap_camera(mut camera_query: Query<(&mut Transform, &mut PixelSnap)>) {
+    for (mut transform, mut snap) in &mut camera_query {
+        transform.translation += snap.remainder.extend(0.0);
+        snap.remainder = Vec2::ZERO;
+    }
+}
+
+fn snap_camera(
+    mut camera_query: Query<(&mut Transform, &mut PixelSnap)>,
+    mut canvas_query: Query<&mut Transform, (With<Canvas>, Without<PixelSnap>)>,
+) {
+    for (mut transform, mut snap) in &mut camera_query {
+        let pos = transform.translation.truncate();
+        let snapped = pos.round();
+        snap.remainder = pos - snapped;
+        transform.translation.x = snapped.x;
+        transform.translation.y = snapped.y;
+
+        for mut canvas_transform in &mut canvas_query {
+            canvas_transform.translation.x = -snap.remainder.x;
+            canvas_transform.translation.y = -snap.remainder.y;
+        }
+    }
+}
+
+/// Scales the [`Canvas`] by the largest integer factor that fits in the window.
+fn fit_canvas(
+    window: Option<Single<&Window, With<PrimaryWindow>>>,
+    pixel_perfect: Res<PixelPerfect>,
+    mut projection_query: Query<&mut Projection, With<CanvasCamera>>,
+) {
+    let Some(window) = window else {
+        return;
+    };
+    let resolution = pixel_perfect.resolution.as_vec2();
+    let scale = (window.width() / resolution.x)
+        .min(window.height() / resolution.y)
+        .floor()
+        .max(1.0);
+    for mut projection in &mut projection_query {
+        if let Projection::Orthographic(ortho) = &mut *projection {
+            ortho.scale = 1.0 / scale;
+        }
+    }
+}
+
+/// Smooth 1D value noise between -1 and 1. Each `seed` gives an unrelated curve.
+fn noise(seed: u32, t: f32) -> f32 {
+    let i = t.floor();
+    let f = t - i;
+    let a = hash(seed, i as i32);
+    let b = hash(seed, i as i32 + 1);
+    let u = f * f * (3.0 - 2.0 * f);
+    a + (b - a) * u
+}
+
+fn hash(seed: u32, n: i32) -> f32 {
+    let mut x = (n as u32).wrapping_mul(0x27d4_eb2d) ^ seed.wrapping_mul(0x9e37_79b9);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x85eb_ca6b);
+    x ^= x >> 13;
+    x as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 200x100 view centered on the camera.
+    const AREA: Rect = Rect {
+        min: Vec2::new(-100.0, -50.0),
+        max: Vec2::new(100.0, 50.0),
+    };
+    const BOUNDS: Rect = Rect {
+        min: Vec2::new(-300.0, -200.0),
+        max: Vec2::new(300.0, 200.0),
+    };
+
+    #[test]
+    fn positions_inside_the_bounds_are_kept() {
+        for pos in [Vec2::ZERO, Vec2::new(200.0, 150.0), Vec2::new(-150.0, 20.0)] {
+            assert_eq!(clamp_to_bounds(pos, AREA, BOUNDS), pos);
+        }
+    }
+
+    #[test]
+    fn view_edges_stop_at_the_bounds() {
+        assert_eq!(
+            clamp_to_bounds(Vec2::new(1000.0, -1000.0), AREA, BOUNDS),
+            Vec2::new(200.0, -150.0)
+        );
+        assert_eq!(
+            clamp_to_bounds(Vec2::new(-250.0, 180.0), AREA, BOUNDS),
+            Vec2::new(-200.0, 150.0)
+        );
+    }
+
+    #[test]
+    fn small_bounds_are_centered_in_the_view() {
+        // Narrower than the view, but taller.
+        let bounds = Rect::new(10.0, -200.0, 90.0, 200.0);
+        assert_eq!(
+            clamp_to_bounds(Vec2::new(-500.0, 500.0), AREA, bounds),
+            Vec2::new(50.0, 150.0)
+        );
+        // A view that is not centered on the camera, e.g. with a moved viewport origin.
+        let area = Rect::new(-150.0, -50.0, 50.0, 50.0);
+        assert_eq!(
+            clamp_to_bounds(Vec2::ZERO, area, Rect::new(0.0, -10.0, 100.0, 10.0)),
+            Vec2::new(100.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn targets_inside_the_deadzone_do_not_move_the_camera() {
+        let follow = FollowCamera::SMOOTH;
+        let camera_pos = Vec2::new(5.0, 5.0);
+        for target_pos in [Vec2::new(20.0, 16.0), Vec2::new(-10.0, -6.0), camera_pos] {
+            assert_eq!(follow.goal(camera_pos, target_pos, Vec2::ZERO), camera_pos);
+        }
+    }
+
+    #[test]
+    fn targets_outside_the_deadzone_pull_it_to_them() {
+        let follow = FollowCamera::SMOOTH;
+        // The target ends up on the edge of the deadzone, on each axis separately.
+        assert_eq!(
+            follow.goal(Vec2::ZERO, Vec2::new(30.0, -4.0), Vec2::ZERO),
+            Vec2::new(14.0, 0.0)
+        );
+        assert_eq!(
+            follow.goal(Vec2::ZERO, Vec2::new(-40.0, 20.0), Vec2::ZERO),
+            Vec2::new(-24.0, 8.0)
+        );
+        // Without a deadzone, the goal is the target.
+        assert_eq!(
+            FollowCamera::TIGHT.goal(Vec2::ZERO, Vec2::new(30.0, -4.0), Vec2::ZERO),
+            Vec2::new(30.0, -4.0)
+        );
+    }
+
+    #[test]
+    fn the_camera_looks_ahead_up_to_a_limit() {
+        let follow = FollowCamera::SMOOTH;
+        // 0.25 s ahead of a target at 100 units per second.
+        assert_eq!(
+            follow.goal(Vec2::ZERO, Vec2::ZERO, Vec2::new(100.0, 0.0)),
+            Vec2::new(25.0 - 16.0, 0.0)
+        );
+        // Capped at 48 units, in the direction of the velocity.
+        let goal = follow.goal(Vec2::ZERO, Vec2::ZERO, Vec2::new(0.0, -1000.0));
+        assert_eq!(goal, Vec2::new(0.0, -48.0 + 12.0));
+    }
+
+    #[test]
+    fn exponential_smoothing_does_not_depend_on_the_frame_rate() {
+        let follow = FollowCamera::SMOOTH;
+        let goal = Vec2::new(100.0, -50.0);
+        let run = |steps: u32| {
+            let mut pos = Vec2::ZERO;
+            for _ in 0..steps {
+                pos = follow.smooth(pos, goal, 0.5 / steps as f32);
+            }
+            pos
+        };
+        let (slow, fast) = (run(15), run(120));
+        assert!(
+            slow.distance(fast) < 1e-3,
+            "{slow} at 30 FPS, {fast} at 240 FPS"
+        );
+        // Half way there after ln(2) / decay rate seconds, and never past the goal.
+        let half_life = std::f32::consts::LN_2 / 6.0;
+        let pos = follow.smooth(Vec2::ZERO, goal, half_life);
+        assert!(pos.distance(goal / 2.0) < 1e-3, "{pos}");
+        assert!(follow.smooth(Vec2::ZERO, goal, 100.0).distance(goal) < 1e-3);
+    }
+
+    #[test]
+    fn lerp_smoothing_speeds_up_with_the_distance() {
+        let follow = FollowCamera::CLASSIC;
+        let dt = 0.01;
+        // Below the threshold, the base speed.
+        let near = Vec2::new(50.0, 0.0);
+        let moved = follow.smooth(Vec2::ZERO, near, dt).length();
+        assert!((moved - FOLLOW_CAMERA_BASE_SPEED * dt * 50.0).abs() < 1e-4);
+        // Twice the threshold away, twice as fast.
+        let far = Vec2::new(0.0, 2.0 * FOLLOW_CAMERA_TRESHOLD);
+        let moved = follow.smooth(Vec2::ZERO, far, dt).length();
+        assert!((moved - 2.0 * FOLLOW_CAMERA_BASE_SPEED * dt * far.y).abs() < 1e-3);
+        // No time, no movement.
+        assert_eq!(follow.smooth(Vec2::ZERO, far, 0.0), Vec2::ZERO);
+    }
+
+    #[test]
+    fn noise_stays_between_minus_one_and_one() {
+        for seed in 0..3 {
+            for i in -1000..1000 {
+                let t = i as f32 * 0.37;
+                let n = noise(seed, t);
+                assert!((-1.0..=1.0).contains(&n), "noise({seed}, {t}) = {n}");
+            }
+        }
+    }
+
+    #[test]
+    fn noise_is_smooth() {
+        // The derivative of the smoothstep is at most 1.5, over a gap of at most 2.
+        let step = 1e-3;
+        for seed in 0..3 {
+            for i in 0..10_000 {
+                let t = i as f32 * step;
+                let change = (noise(seed, t + step) - noise(seed, t)).abs();
+                assert!(
+                    change <= 3.0 * step + 1e-5,
+                    "noise({seed}, {t}) jumps by {change}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn noise_seeds_give_different_curves() {
+        let curve = |seed| {
+            (0..16)
+                .map(|i| noise(seed, i as f32 + 0.5))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(curve(0), curve(0), "the same seed gives the same curve");
+        assert_ne!(curve(0), curve(1));
+        assert_ne!(curve(1), curve(2));
+        // Not flat either.
+        assert!(curve(0).iter().any(|n| (n - curve(0)[0]).abs() > 0.1));
+    }
+}
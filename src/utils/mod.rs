@@ -1,9 +1,20 @@
 //! Stuffs that haven't been on the three main directories in the original template
+pub mod animation_set;
+pub mod animator;
+#[cfg(feature = "aseprite")]
+pub mod aseprite;
 pub mod cam;
 pub mod tiled; // Named to be distinct from bevy::camera
 
 use bevy::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins((tiled::plugin, cam::plugin));
+    app.add_plugins((
+        tiled::plugin,
+        cam::plugin,
+        animation_set::plugin,
+        animator::plugin,
+    ));
+    #[cfg(feature = "aseprite")]
+    app.add_plugins(aseprite::plugin);
 }
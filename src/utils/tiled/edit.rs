@@ -0,0 +1,277 @@
+//! Edit the tiles of a spawned map at runtime, e.g. for breakable walls or doors that open.
+//!
+//! The edits are [`Command`]s (`commands.queue(RemoveTile { .. })`), so they keep [`TileStorage`],
+//! [`TileTextureIndex`] and the tile's colliders in sync no matter which system queues them.
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::utils::tiled::{TiledLayer, TiledMap, TiledMapHandle, TiledTile, spawn_tile};
+
+/// Places a tile, replacing whatever was at its position in the layer.
+#[derive(Debug, Clone, Copy)]
+pub struct SetTile {
+    /// The entity holding the [`TiledMapHandle`].
+    pub map: Entity,
+    /// Index of the layer in [`tiled::Map::layers`].
+    pub layer_index: u32,
+    pub tile_pos: TilePos,
+    pub tile: TiledTile,
+    pub flip: TileFlip,
+}
+
+impl Command for SetTile {
+    fn apply(self, world: &mut World) {
+        let Some(handle) = world.get::<TiledMapHandle>(self.map).map(|h| h.0.clone()) else {
+            warn!("Cannot set a tile of {}: it is not a map.", self.map);
+            return;
+        };
+        let Some(layer_entity) = world
+            .query::<(Entity, &TiledLayer)>()
+            .iter(world)
+            .find(|(_, layer)| {
+                layer.map == self.map
+                    && layer.layer_index == self.layer_index
+                    && layer.tileset_index == self.tile.tileset_index
+            })
+            .map(|(entity, _)| entity)
+        else {
+            warn!(
+                "Cannot set a tile of layer {} with tileset {}: no such tilemap was spawned.",
+                self.layer_index, self.tile.tileset_index
+            );
+            return;
+        };
+        let has_image = world
+            .resource::<Assets<TiledMap>>()
+            .get(&handle)
+            .and_then(|tiled_map| tiled_map.texture_index(self.tile.tileset_index, self.tile.id))
+            .is_some();
+        if !has_image {
+            warn!(
+                "Cannot set tile {} of tileset {}: it has no image.",
+                self.tile.id, self.tile.tileset_index
+            );
+            return;
+        }
+
+        // Only replace the tile once the new one is known to spawn.
+        remove_tile(world, self.map, self.layer_index, &self.tile_pos);
+        let tile_entity = world.resource_scope(|world, maps: Mut<Assets<TiledMap>>| {
+            let tiled_map = maps.get(&handle)?;
+            let mut commands = world.commands();
+            spawn_tile(
+                &mut commands,
+                tiled_map,
+                layer_entity,
+                self.tile_pos,
+                self.tile,
+                self.flip,
+            )
+        });
+        world.flush();
+
+        if let (Some(tile_entity), Some(mut storage)) =
+            (tile_entity, world.get_mut::<TileStorage>(layer_entity))
+        {
+            storage.set(&self.tile_pos, tile_entity);
+        }
+    }
+}
+
+/// Removes the tile at a position in a layer, along with its colliders.
+#[derive(Debug, Clone, Copy)]
+pub struct RemoveTile {
+    /// The entity holding the [`TiledMapHandle`].
+    pub map: Entity,
+    /// Index of the layer in [`tiled::Map::layers`].
+    pub layer_index: u32,
+    pub tile_pos: TilePos,
+}
+
+impl Command for RemoveTile {
+    fn apply(self, world: &mut World) {
+        remove_tile(world, self.map, self.layer_index, &self.tile_pos);
+    }
+}
+
+/// A Tiled layer is spawned as one tilemap per tileset, so the tile may be in any of them.
+fn remove_tile(world: &mut World, map: Entity, layer_index: u32, tile_pos: &TilePos) {
+    let mut removed = Vec::new();
+    let mut layer_query = world.query::<(&TiledLayer, &mut TileStorage)>();
+    for (layer, mut storage) in layer_query.iter_mut(world) {
+        if layer.map != map || layer.layer_index != layer_index {
+            continue;
+        }
+        if let Some(tile_entity) = storage.get(tile_pos) {
+            storage.remove(tile_pos);
+            removed.push(tile_entity);
+        }
+    }
+    // The colliders are children of the tile.
+    for tile_entity in removed {
+        world.despawn(tile_entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use avian2d::prelude::Collider;
+
+    use super::*;
+    use crate::utils::tiled::{
+        TiledLayersStorage,
+        testing::{SMALL_MAP, app, load_map, spawn_map},
+    };
+
+    /// A wall of [`SMALL_MAP`], in its top-left corner.
+    const WALL_POS: TilePos = TilePos { x: 0, y: 1 };
+    const WALLS: u32 = 1;
+
+    fn walls_layer(world: &World, map: Entity) -> Entity {
+        world.get::<TiledLayersStorage>(map).unwrap().storage[&WALLS]
+    }
+
+    /// Every tile of the walls layer, checking that the tile entities agree with the storage.
+    fn walls(world: &World, map: Entity) -> Vec<Option<tiled::TileId>> {
+        let layer_entity = walls_layer(world, map);
+        let storage = world.get::<TileStorage>(layer_entity).unwrap();
+        let mut tiles = Vec::new();
+        for y in 0..storage.size.y {
+            for x in 0..storage.size.x {
+                let tile_pos = TilePos { x, y };
+                tiles.push(storage.get(&tile_pos).map(|tile_entity| {
+                    assert_eq!(world.get::<TilePos>(tile_entity), Some(&tile_pos));
+                    assert_eq!(
+                        world.get::<TilemapId>(tile_entity),
+                        Some(&TilemapId(layer_entity))
+                    );
+                    let tile = world.get::<TiledTile>(tile_entity).unwrap();
+                    assert_eq!(tile.tileset_index, 0);
+                    assert_eq!(
+                        world.get::<TileTextureIndex>(tile_entity),
+                        Some(&TileTextureIndex(tile.id))
+                    );
+                    tile.id
+                }));
+            }
+        }
+        tiles
+    }
+
+    fn collider_count(world: &mut World) -> usize {
+        world
+            .query_filtered::<(), With<Collider>>()
+            .iter(world)
+            .count()
+    }
+
+    fn apply(app: &mut App, command: impl Command) {
+        app.world_mut().commands().queue(command);
+        app.world_mut().flush();
+    }
+
+    #[test]
+    fn removed_then_set_tiles_are_restored() {
+        let mut app = app();
+        let map = spawn_map(&mut app, load_map(SMALL_MAP.as_bytes(), "small.tmx"));
+        let world = app.world_mut();
+        let walls_before = walls(world, map);
+        assert_eq!(
+            walls_before,
+            [None, None, Some(1), Some(1), None, None],
+            "rows go up from the bottom"
+        );
+        let colliders_before = collider_count(world);
+        assert_eq!(colliders_before, 2);
+        let wall = world
+            .get::<TileStorage>(walls_layer(world, map))
+            .unwrap()
+            .get(&WALL_POS)
+            .unwrap();
+
+        apply(
+            &mut app,
+            RemoveTile {
+                map,
+                layer_index: WALLS,
+                tile_pos: WALL_POS,
+            },
+        );
+        let world = app.world_mut();
+        assert_eq!(walls(world, map), [None, None, Some(1), None, None, None]);
+        assert!(world.get_entity(wall).is_err(), "the tile is despawned");
+        assert_eq!(collider_count(world), 1, "its collider is despawned");
+
+        apply(
+            &mut app,
+            SetTile {
+                map,
+                layer_index: WALLS,
+                tile_pos: WALL_POS,
+                tile: TiledTile {
+                    tileset_index: 0,
+                    id: 1,
+                },
+                flip: TileFlip::default(),
+            },
+        );
+        let world = app.world_mut();
+        assert_eq!(walls(world, map), walls_before);
+        assert_eq!(collider_count(world), colliders_before);
+    }
+
+    #[test]
+    fn set_tiles_replace_the_previous_one() {
+        let mut app = app();
+        let map = spawn_map(&mut app, load_map(SMALL_MAP.as_bytes(), "small.tmx"));
+        let tile_count = app
+            .world_mut()
+            .query::<&TiledTile>()
+            .iter(app.world())
+            .count();
+
+        apply(
+            &mut app,
+            SetTile {
+                map,
+                layer_index: WALLS,
+                tile_pos: WALL_POS,
+                tile: TiledTile {
+                    tileset_index: 0,
+                    id: 3,
+                },
+                flip: TileFlip::default(),
+            },
+        );
+        let world = app.world_mut();
+        assert_eq!(
+            walls(world, map),
+            [None, None, Some(1), Some(3), None, None]
+        );
+        assert_eq!(world.query::<&TiledTile>().iter(world).count(), tile_count);
+        assert_eq!(collider_count(world), 1, "tile 3 has no collider");
+    }
+
+    #[test]
+    fn tiles_of_unspawned_tilesets_are_not_set() {
+        let mut app = app();
+        let map = spawn_map(&mut app, load_map(SMALL_MAP.as_bytes(), "small.tmx"));
+        let walls_before = walls(app.world(), map);
+
+        apply(
+            &mut app,
+            SetTile {
+                map,
+                layer_index: WALLS,
+                tile_pos: WALL_POS,
+                tile: TiledTile {
+                    tileset_index: 1,
+                    id: 0,
+                },
+                flip: TileFlip::default(),
+            },
+        );
+        assert_eq!(walls(app.world(), map), walls_before);
+    }
+}
@@ -11,9 +11,17 @@
 //
 // Functional limitations:
 //   * When the 'atlas' feature is enabled tilesets using a collection of images will be skipped.
-//   * Only finite tile layers are loaded. Infinite tile layers and object layers will be skipped.
+//   * Only finite tile layers are loaded. Infinite tile layers will be skipped.
+//   * Objects of object layers are spawned as bare [`TiledObject`] entities, which other modules
+//     turn into game entities by their class.
+pub mod edit;
+pub mod query;
 mod shaper;
+// Only the native dev tools save maps.
+#[cfg(all(feature = "dev", not(target_family = "wasm")))]
+pub mod writer;
 
+use std::collections::VecDeque;
 use std::io::Cursor;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -45,16 +53,19 @@ use tiled::{ObjectShape, Tileset};
 use crate::{
     // TODO: asset_tracking::LoadResource,
     demo::player::PLAYER_Z_TRANSLATION,
+    screens::Screen,
     utils::tiled::shaper::{PreSharedShape, shaper},
 };
 
 pub(super) fn plugin(app: &mut App) {
     app.init_asset::<TiledMap>()
         .register_asset_loader(TiledLoader)
-        .add_systems(Update, process_loaded_maps);
+        .init_resource::<MapSpawnBudget>()
+        .add_systems(Update, (process_loaded_maps, spawn_map_tiles).chain())
+        .add_observer(despawn_removed_map);
 }
 
-/// [`crate:::screens::gameplay`]
+/// [`crate::screens::loading`]
 pub fn spawn_tiled_map<const MAP_NUMBER: usize>(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
@@ -67,10 +78,13 @@ pub fn spawn_tiled_map<const MAP_NUMBER: usize>(
             panic!("No such map number exists");
         }
     };
-    commands.spawn(TiledMapBundle {
-        tiled_map: TiledMapHandle(asset_server.load(asset_path)),
-        ..Default::default()
-    });
+    commands.spawn((
+        TiledMapBundle {
+            tiled_map: TiledMapHandle(asset_server.load(asset_path)),
+            ..Default::default()
+        },
+        DespawnOnExit(Screen::Gameplay),
+    ));
 }
 
 #[derive(TypePath, Asset)]
@@ -86,10 +100,212 @@ pub struct TiledMap {
     pub tile_image_offsets: HashMap<(usize, tiled::TileId), u32>,
 }
 
+impl TiledMap {
+    /// Index of a tile's image in the [`TilemapTexture`] of its tileset.
+    pub fn texture_index(&self, tileset_index: usize, tile_id: tiled::TileId) -> Option<u32> {
+        match self.tilemap_textures.get(&tileset_index)? {
+            TilemapTexture::Single(_) => Some(tile_id),
+            #[cfg(not(feature = "atlas"))]
+            TilemapTexture::Vector(_) => self
+                .tile_image_offsets
+                .get(&(tileset_index, tile_id))
+                .copied(),
+            #[cfg(not(feature = "atlas"))]
+            _ => unreachable!(),
+        }
+    }
+}
+
 // Stores a list of tiled layers.
+// A layer using several tilesets is spawned as several tilemaps, see [`TiledLayer`]; the one
+// stored here is the last one holding tiles.
 #[derive(Component, Default)]
 pub struct TiledLayersStorage {
     pub storage: HashMap<u32, Entity>,
+    /// The same tilemaps, by layer name.
+    pub by_name: HashMap<String, Entity>,
+}
+
+/// Metadata of a map, inserted on its entity once the map asset is loaded.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct TiledMapInfo {
+    /// The world-space rectangle covered by the map.
+    pub bounds: Rect,
+    /// Size of a grid cell in world units.
+    pub tile_size: Vec2,
+    /// Size of the map in tiles.
+    pub size: UVec2,
+    pub orientation: tiled::Orientation,
+}
+
+/// Triggered on a map entity once all of its tiles and colliders are spawned.
+#[derive(EntityEvent, Debug, Clone, Copy)]
+pub struct TiledMapSpawned {
+    pub entity: Entity,
+}
+
+/// A spawned tilemap layer. Tiled allows mixing tilesets in one layer, so there is one of these
+/// for each combination of Tiled layer and tileset.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct TiledLayer {
+    /// The entity holding the [`TiledMapHandle`] this layer was spawned from.
+    pub map: Entity,
+    /// Index of the layer in [`tiled::Map::layers`].
+    pub layer_index: u32,
+    /// Index of the tileset in [`tiled::Map::tilesets`] every tile of this layer comes from.
+    pub tileset_index: usize,
+}
+
+/// The Tiled tile a tile entity was spawned from.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct TiledTile {
+    pub tileset_index: usize,
+    pub id: tiled::TileId,
+}
+
+/// An object of an object layer, spawned where it sits in the map. It has no sprite or collider:
+/// other modules give it those depending on its class, e.g. props.
+#[derive(Component, Debug, Clone)]
+pub struct TiledObject {
+    /// The entity holding the [`TiledMapHandle`] this object was spawned from.
+    pub map: Entity,
+    /// Id of the object in the map, which object properties of other objects refer to.
+    pub id: u32,
+    pub name: String,
+    /// The class of the object, called "type" before Tiled 1.9.
+    pub class: String,
+    pub shape: ObjectShape,
+    pub properties: tiled::Properties,
+}
+
+impl TiledObject {
+    /// The points of a polyline or polygon, relative to the object position with y up.
+    pub fn points(&self) -> Vec<Vec2> {
+        match &self.shape {
+            ObjectShape::Polyline { points } | ObjectShape::Polygon { points } => {
+                points.iter().map(|&(x, y)| Vec2::new(x, -y)).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Position of an object from the map center: the center of rectangles, ellipses and tile
+/// objects, and the origin of other shapes. Rotation is ignored.
+pub fn object_position(map: &tiled::Map, layer: &tiled::Layer, object: &tiled::ObjectData) -> Vec2 {
+    let mut position = Vec2::new(object.x + layer.offset_x, object.y + layer.offset_y);
+    if let ObjectShape::Rect { width, height } | ObjectShape::Ellipse { width, height } =
+        object.shape
+    {
+        // Tile objects hang from their bottom left corner, other ones from their top left one.
+        let half_height = if object.tile.is_some() {
+            -height / 2.0
+        } else {
+            height / 2.0
+        };
+        position += Vec2::new(width / 2.0, half_height);
+    }
+    Vec2::new(
+        position.x - (map.width * map.tile_width) as f32 / 2.0,
+        (map.height * map.tile_height) as f32 / 2.0 - position.y,
+    )
+}
+
+/// Spawns the objects of every object layer of a map.
+fn spawn_objects(commands: &mut Commands, map_entity: Entity, map: &tiled::Map) {
+    for (layer_index, layer) in map.layers().enumerate() {
+        let tiled::LayerType::Objects(object_layer) = layer.layer_type() else {
+            continue;
+        };
+        for object in object_layer.object_data() {
+            let position = object_position(map, &layer, object);
+            let name = if object.name.is_empty() {
+                format!("Tiled Object {}", object.id())
+            } else {
+                object.name.clone()
+            };
+            commands.spawn((
+                Name::new(name),
+                TiledObject {
+                    map: map_entity,
+                    id: object.id(),
+                    name: object.name.clone(),
+                    class: object.user_type.clone(),
+                    shape: object.shape.clone(),
+                    properties: object.properties.clone(),
+                },
+                Transform::from_translation(position.extend(layer_index as f32)),
+                Visibility::default(),
+            ));
+        }
+    }
+}
+
+/// Offset of the bottom-left corner of the tile at `tile_pos` from the map center.
+/// Maps are spawned with [`TilemapAnchor::Center`], so this is also its world position for an
+/// unmoved map.
+pub fn tile_corner(map: &tiled::Map, tile_size: Vec2, tile_pos: &TilePos) -> Vec2 {
+    let half_map_width = map.width as f32 / 2.0;
+    let half_map_height = map.height as f32 / 2.0;
+    Vec2::new(
+        tile_size.x * (tile_pos.x as f32 - half_map_width),
+        tile_size.y * (tile_pos.y as f32 - half_map_height),
+    )
+}
+
+/// Spawns a tile of the tilemap `layer_entity`, along with the colliders Tiled defines for it.
+/// The caller is responsible for putting it in the layer's [`TileStorage`].
+///
+/// Returns `None` if the tile has no image in its tileset.
+pub fn spawn_tile(
+    commands: &mut Commands,
+    tiled_map: &TiledMap,
+    layer_entity: Entity,
+    tile_pos: TilePos,
+    tile: TiledTile,
+    flip: TileFlip,
+) -> Option<Entity> {
+    let tileset = &tiled_map.map.tilesets()[tile.tileset_index];
+    let texture_index = tiled_map.texture_index(tile.tileset_index, tile.id)?;
+
+    let tile_entity = commands
+        .spawn((
+            TileBundle {
+                position: tile_pos,
+                tilemap_id: TilemapId(layer_entity),
+                texture_index: TileTextureIndex(texture_index),
+                flip,
+                ..Default::default()
+            },
+            tile,
+            Transform::default(),
+        ))
+        .id();
+    // TODO: bundle-wise work instead
+    if tileset.spacing != 0 {
+        panic!("Don't do that please ;o");
+    };
+
+    let corner = tile_corner(
+        &tiled_map.map,
+        Vec2::new(tileset.tile_width as f32, tileset.tile_height as f32),
+        &tile_pos,
+    );
+    if let Some(pre_shared_shape) = tiled_map.pre_colliders.get(&tile.id) {
+        for obj in pre_shared_shape.iter() {
+            use ObjectShape::*;
+            let offset = match &obj.shape {
+                Rect { width, height } => Vec2::new(obj.x + width / 2.0, obj.y + height / 2.0),
+                _ => Vec2::new(obj.x, obj.y),
+            };
+            commands.entity(tile_entity).with_child((
+                Transform::from_translation((corner + offset).extend(PLAYER_Z_TRANSLATION)),
+                Collider::from(shaper(&obj.shape)),
+                ColliderOf { body: layer_entity },
+            ));
+        }
+    }
+    Some(tile_entity)
 }
 
 #[derive(Component, Default)]
@@ -99,6 +315,7 @@ pub struct TiledMapHandle(pub Handle<TiledMap>);
 pub struct TiledMapBundle {
     pub tiled_map: TiledMapHandle,
     pub storage: TiledLayersStorage,
+    pub progress: MapSpawnProgress,
     pub transform: Transform,
     pub global_transform: GlobalTransform,
     pub render_settings: TilemapRenderSettings,
@@ -163,41 +380,7 @@ impl AssetLoader for TiledLoader {
             .load_tmx_map(load_context.path().path())
             .map_err(|e| std::io::Error::other(format!("Could not load TMX map: {e}")))?;
 
-        let mut pre_colliders = HashMap::<tiled::TileId, PreSharedShape>::new();
-        for tileset in map.tilesets() {
-            for (tile_id, tile_data) in tileset.tiles() {
-                if let Some(obj_layer_data_collision) = &tile_data.collision {
-                    //let mut rects = Vec::new();
-                    let pre_shared_shape = PreSharedShape::from_object_data(
-                        tile_id,
-                        obj_layer_data_collision.object_data(),
-                    );
-                    /*
-                    for collision_obj_data in  {
-                        //objs.push(*collision_obj_data);
-                        //info!("Object data shape: {:?}", collision_obj_data.shape);
-
-                        match collision_obj_data.shape {
-                            Rect {width, height} => {
-                                rects.push((
-                                    collision_obj_data.x,
-                                    collision_obj_data.y,
-                                    width,
-                                    height,
-                                ));
-
-                            },
-                            // TODO other shapes
-                            _ => { },
-                        }
-                    }
-                    */
-                    if !pre_shared_shape.is_empty() {
-                        pre_colliders.insert(tile_id, pre_shared_shape);
-                    }
-                }
-            }
-        }
+        let pre_colliders = pre_colliders(&map);
         let mut tilemap_textures = HashMap::default();
         #[cfg(not(feature = "atlas"))]
         let mut tile_image_offsets = HashMap::default();
@@ -299,6 +482,46 @@ impl AssetLoader for TiledLoader {
     }
 }
 
+/// The colliders Tiled defines for each tile, by tile id.
+fn pre_colliders(map: &tiled::Map) -> HashMap<tiled::TileId, PreSharedShape> {
+    let mut pre_colliders = HashMap::<tiled::TileId, PreSharedShape>::new();
+    for tileset in map.tilesets() {
+        for (tile_id, tile_data) in tileset.tiles() {
+            if let Some(obj_layer_data_collision) = &tile_data.collision {
+                //let mut rects = Vec::new();
+                let pre_shared_shape = PreSharedShape::from_object_data(
+                    tile_id,
+                    obj_layer_data_collision.object_data(),
+                );
+                /*
+                for collision_obj_data in  {
+                    //objs.push(*collision_obj_data);
+                    //info!("Object data shape: {:?}", collision_obj_data.shape);
+
+                    match collision_obj_data.shape {
+                        Rect {width, height} => {
+                            rects.push((
+                                collision_obj_data.x,
+                                collision_obj_data.y,
+                                width,
+                                height,
+                            ));
+
+                        },
+                        // TODO other shapes
+                        _ => { },
+                    }
+                }
+                */
+                if !pre_shared_shape.is_empty() {
+                    pre_colliders.insert(tile_id, pre_shared_shape);
+                }
+            }
+        }
+    }
+    pre_colliders
+}
+
 /// Find the common parent directory
 /// "/foo/bar/one", "/foo/bar/two" => "/foo/bar"
 fn common_parent(path1: &PathBuf, path2: &PathBuf) -> Result<PathBuf, ()> {
@@ -321,8 +544,10 @@ fn process_loaded_maps(
     mut commands: Commands,
     mut map_events: MessageReader<AssetEvent<TiledMap>>,
     maps: Res<Assets<TiledMap>>,
-    tile_storage_query: Query<(Entity, &TileStorage)>,
+    layer_query: Query<(Entity, &TiledLayer, &TileStorage)>,
+    object_query: Query<(Entity, &TiledObject)>,
     mut map_query: Query<(
+        Entity,
         &TiledMapHandle,
         &mut TiledLayersStorage,
         &TilemapRenderSettings,
@@ -356,21 +581,41 @@ fn process_loaded_maps(
     }
 
     for changed_map in changed_maps.iter() {
-        for (map_handle, mut layer_storage, render_settings) in map_query.iter_mut() {
+        for (map_entity, map_handle, mut layer_storage, render_settings) in map_query.iter_mut() {
             // only deal with currently changed map
             if map_handle.0.id() != *changed_map {
                 continue;
             }
             if let Some(tiled_map) = maps.get(&map_handle.0) {
-                // TODO: Create a RemoveMap component..
-                for layer_entity in layer_storage.storage.values() {
-                    if let Ok((_, layer_tile_storage)) = tile_storage_query.get(*layer_entity) {
-                        for tile in layer_tile_storage.iter().flatten() {
-                            commands.entity(*tile).despawn()
-                        }
-                    }
-                    // commands.entity(*layer_entity).despawn_recursive();
-                }
+                despawn_layers(&mut commands, map_entity, &layer_query);
+                despawn_objects(&mut commands, map_entity, &object_query);
+                layer_storage.storage.clear();
+                layer_storage.by_name.clear();
+
+                let grid_size = Vec2::new(
+                    tiled_map.map.tile_width as f32,
+                    tiled_map.map.tile_height as f32,
+                );
+                let map_size = UVec2::new(tiled_map.map.width, tiled_map.map.height);
+                let map_info = TiledMapInfo {
+                    bounds: Rect::from_corners(
+                        tile_corner(&tiled_map.map, grid_size, &TilePos { x: 0, y: 0 }),
+                        tile_corner(
+                            &tiled_map.map,
+                            grid_size,
+                            &TilePos {
+                                x: map_size.x,
+                                y: map_size.y,
+                            },
+                        ),
+                    ),
+                    tile_size: grid_size,
+                    size: map_size,
+                    orientation: tiled_map.map.orientation,
+                };
+
+                let mut job = MapSpawnJob::default();
+                let mut total = 0;
 
                 // The TilemapBundle requires that all tile images come exclusively from a single
                 // tiled texture or from a Vec of independent per-tile images. Furthermore, all of
@@ -395,6 +640,7 @@ fn process_loaded_maps(
                     };
 
                     // Once materials have been created/added we need to then create the layers.
+                    // Their tiles are spawned over the next frames by `spawn_map_tiles`.
                     for (layer_index, layer) in tiled_map.map.layers().enumerate() {
                         let offset_x = layer.offset_x;
                         let offset_y = layer.offset_y;
@@ -438,157 +684,388 @@ fn process_loaded_maps(
                             tiled::Orientation::Orthogonal => TilemapType::Square,
                         };
 
-                        let mut tile_storage = TileStorage::empty(map_size);
-                        let layer_entity = commands.spawn_empty().id();
-
-                        for x in 0..map_size.x {
-                            for y in 0..map_size.y {
-                                // Transform TMX coords into bevy coords.
-                                let mapped_y = tiled_map.map.height - 1 - y;
-
-                                let mapped_x = x as i32;
-                                let mapped_y = mapped_y as i32;
-
-                                let layer_tile = match layer_data.get_tile(mapped_x, mapped_y) {
-                                    Some(t) => t,
-                                    None => {
-                                        continue;
-                                    }
-                                };
-                                if tileset_index != layer_tile.tileset_index() {
-                                    continue;
-                                }
-                                let layer_tile_data =
-                                    match layer_data.get_tile_data(mapped_x, mapped_y) {
-                                        Some(d) => d,
-                                        None => {
-                                            continue;
-                                        }
-                                    };
-
-                                let texture_index = match tilemap_texture {
-                                    TilemapTexture::Single(_) => layer_tile.id(),
-                                    #[cfg(not(feature = "atlas"))]
-                                    TilemapTexture::Vector(_) =>
-                                        *tiled_map.tile_image_offsets.get(&(tileset_index, layer_tile.id()))
-                                        .expect("The offset into to image vector should have been saved during the initial load."),
-                                    #[cfg(not(feature = "atlas"))]
-                                    _ => unreachable!()
-                                };
-
-                                let tile_pos = TilePos { x, y };
-                                let tile_entity = commands
-                                    .spawn((
-                                        TileBundle {
-                                            position: tile_pos,
-                                            tilemap_id: TilemapId(layer_entity),
-                                            texture_index: TileTextureIndex(texture_index),
-                                            flip: TileFlip {
-                                                x: layer_tile_data.flip_h,
-                                                y: layer_tile_data.flip_v,
-                                                d: layer_tile_data.flip_d,
-                                            },
-                                            ..Default::default()
-                                        },
-                                        Transform::default(),
-                                    ))
-                                    .id();
-                                // TODO: bundle-wise work instead
-                                if tileset.spacing != 0 {
-                                    panic!("Don't do that please ;o");
-                                };
-
-                                let tile_width = tileset.tile_width as f32;
-                                let tile_height = tileset.tile_height as f32;
-                                let half_map_width = tiled_map.map.width as f32 / 2.0;
-                                let half_map_height = tiled_map.map.height as f32 / 2.0;
-
-                                let mut colliders = Vec::<Entity>::new(); // This has no use for now
-                                if let Some(pre_shared_shape) =
-                                    tiled_map.pre_colliders.get(&layer_tile.id())
-                                {
-                                    for obj in pre_shared_shape.iter() {
-                                        use ObjectShape::*;
-                                        match &obj.shape {
-                                            Rect { width, height } => colliders.push(
-                                                commands
-                                                    .entity(tile_entity)
-                                                    .with_child((
-                                                        Transform::from_translation(
-                                                            Vec3::new(
-                                                                tile_width
-                                                                    * (x as f32 - half_map_width),
-                                                                tile_height
-                                                                    * (y as f32 - half_map_height),
-                                                                0.,
-                                                            ) + Vec3::new(
-                                                                obj.x + width / 2.0,
-                                                                obj.y + height / 2.0,
-                                                                PLAYER_Z_TRANSLATION,
-                                                            ),
-                                                        ),
-                                                        Collider::from(shaper(&obj.shape)),
-                                                        ColliderOf { body: layer_entity },
-                                                    ))
-                                                    .id(),
-                                            ),
-                                            _ => colliders.push(
-                                                commands
-                                                    .entity(tile_entity)
-                                                    .with_child((
-                                                        Transform::from_translation(
-                                                            Vec3::new(
-                                                                tile_width
-                                                                    * (x as f32 - half_map_width),
-                                                                tile_height
-                                                                    * (y as f32 - half_map_height),
-                                                                0.,
-                                                            ) + Vec3::new(
-                                                                obj.x,
-                                                                obj.y,
-                                                                PLAYER_Z_TRANSLATION,
-                                                            ),
-                                                        ),
-                                                        Collider::from(shaper(&obj.shape)),
-                                                        ColliderOf { body: layer_entity },
-                                                    ))
-                                                    .id(),
-                                            ),
-                                        }
-                                    }
-                                }
-                                //if !colliders.is_empty() {}
-                                tile_storage.set(&tile_pos, tile_entity);
-                            }
-                        }
+                        let tile_count = (0..map_size.x as i32)
+                            .flat_map(|x| (0..map_size.y as i32).map(move |y| (x, y)))
+                            .filter_map(|(x, y)| layer_data.get_tile(x, y))
+                            .filter(|layer_tile| layer_tile.tileset_index() == tileset_index)
+                            .count() as u32;
 
-                        commands.entity(layer_entity).insert((
-                            TilemapBundle {
-                                grid_size,
-                                size: map_size,
-                                storage: tile_storage,
-                                texture: tilemap_texture.clone(),
-                                tile_size,
-                                spacing: tile_spacing,
-                                anchor: TilemapAnchor::Center,
-                                transform: Transform::from_xyz(
-                                    offset_x,
-                                    -offset_y,
-                                    layer_index as f32,
-                                ),
-                                map_type,
-                                render_settings: *render_settings,
-                                ..Default::default()
-                            },
-                            RigidBody::Static,
-                        ));
+                        // Spawned even without tiles, so that runtime edits can place some.
+                        let layer_entity = commands
+                            .spawn((
+                                TilemapBundle {
+                                    grid_size,
+                                    size: map_size,
+                                    storage: TileStorage::empty(map_size),
+                                    texture: tilemap_texture.clone(),
+                                    tile_size,
+                                    spacing: tile_spacing,
+                                    anchor: TilemapAnchor::Center,
+                                    transform: Transform::from_xyz(
+                                        offset_x,
+                                        -offset_y,
+                                        layer_index as f32,
+                                    ),
+                                    map_type,
+                                    render_settings: *render_settings,
+                                    ..Default::default()
+                                },
+                                RigidBody::Static,
+                                TiledLayer {
+                                    map: map_entity,
+                                    layer_index: layer_index as u32,
+                                    tileset_index,
+                                },
+                            ))
+                            .id();
 
-                        layer_storage
-                            .storage
-                            .insert(layer_index as u32, layer_entity);
+                        if tile_count > 0
+                            || !layer_storage.storage.contains_key(&(layer_index as u32))
+                        {
+                            layer_storage
+                                .storage
+                                .insert(layer_index as u32, layer_entity);
+                            layer_storage
+                                .by_name
+                                .insert(layer.name.clone(), layer_entity);
+                        }
+
+                        if tile_count > 0 {
+                            total += tile_count;
+                            job.pending.push_back(PendingLayer {
+                                layer_entity,
+                                layer_index,
+                                tileset_index,
+                                next_cell: 0,
+                            });
+                        }
                     }
                 }
+
+                spawn_objects(&mut commands, map_entity, &tiled_map.map);
+
+                commands.entity(map_entity).insert((
+                    job,
+                    map_info,
+                    MapSpawnProgress {
+                        spawned: 0,
+                        total,
+                        built: false,
+                    },
+                ));
+            }
+        }
+    }
+}
+
+/// How many tiles are spawned per frame across all maps, so that large maps don't hitch the
+/// frame they are spawned in.
+#[derive(Resource, Debug, Clone, Copy, Reflect)]
+#[reflect(Resource)]
+pub struct MapSpawnBudget {
+    pub tiles_per_frame: u32,
+}
+
+impl Default for MapSpawnBudget {
+    fn default() -> Self {
+        Self {
+            tiles_per_frame: 1024,
+        }
+    }
+}
+
+/// How far a map is from being fully spawned.
+#[derive(Component, Debug, Default, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct MapSpawnProgress {
+    pub spawned: u32,
+    pub total: u32,
+    built: bool,
+}
+
+impl MapSpawnProgress {
+    /// Whether every tile and collider of the map has been spawned.
+    pub fn is_built(&self) -> bool {
+        self.built
+    }
+
+    /// Between 0 and 1.
+    pub fn fraction(&self) -> f32 {
+        if self.built {
+            1.0
+        } else if self.total == 0 {
+            0.0
+        } else {
+            self.spawned as f32 / self.total as f32
+        }
+    }
+}
+
+/// Whether every spawned map is fully built. Use this to hold off gameplay.
+pub fn all_maps_built(progress_query: Query<&MapSpawnProgress>) -> bool {
+    progress_query.iter().all(MapSpawnProgress::is_built)
+}
+
+/// The tilemap layers of a map whose tiles are yet to be spawned.
+#[derive(Component, Default)]
+struct MapSpawnJob {
+    pending: VecDeque<PendingLayer>,
+}
+
+struct PendingLayer {
+    layer_entity: Entity,
+    layer_index: usize,
+    tileset_index: usize,
+    /// The next cell of the layer to look at, in row-major order from the bottom.
+    next_cell: u32,
+}
+
+fn spawn_map_tiles(
+    mut commands: Commands,
+    budget: Res<MapSpawnBudget>,
+    maps: Res<Assets<TiledMap>>,
+    mut map_query: Query<(
+        Entity,
+        &TiledMapHandle,
+        &mut MapSpawnJob,
+        &mut MapSpawnProgress,
+    )>,
+    mut storage_query: Query<&mut TileStorage>,
+) {
+    let mut budget = budget.tiles_per_frame;
+    for (map_entity, map_handle, mut job, mut progress) in &mut map_query {
+        let Some(tiled_map) = maps.get(&map_handle.0) else {
+            continue;
+        };
+
+        while budget > 0 {
+            let Some(pending) = job.pending.front_mut() else {
+                break;
+            };
+            let layer_done = spawn_pending_tiles(
+                &mut commands,
+                tiled_map,
+                pending,
+                &mut storage_query,
+                &mut budget,
+                &mut progress,
+            );
+            if layer_done {
+                job.pending.pop_front();
+            }
+        }
+
+        if job.pending.is_empty() {
+            info!("Map spawned!");
+            progress.built = true;
+            commands.entity(map_entity).remove::<MapSpawnJob>();
+            commands.trigger(TiledMapSpawned { entity: map_entity });
+        }
+    }
+}
+
+/// Spawns tiles of a layer until it is done or the budget is spent. Returns whether it is done.
+fn spawn_pending_tiles(
+    commands: &mut Commands,
+    tiled_map: &TiledMap,
+    pending: &mut PendingLayer,
+    storage_query: &mut Query<&mut TileStorage>,
+    budget: &mut u32,
+    progress: &mut MapSpawnProgress,
+) -> bool {
+    let Ok(mut tile_storage) = storage_query.get_mut(pending.layer_entity) else {
+        warn!("Skipped spawning the tiles of a despawned layer.");
+        return true;
+    };
+    let Some(tiled::LayerType::Tiles(tiled::TileLayer::Finite(layer_data))) = tiled_map
+        .map
+        .get_layer(pending.layer_index)
+        .map(|layer| layer.layer_type())
+    else {
+        return true;
+    };
+
+    let (width, height) = (tiled_map.map.width, tiled_map.map.height);
+    while *budget > 0 && pending.next_cell < width * height {
+        let x = pending.next_cell % width;
+        let y = pending.next_cell / width;
+        pending.next_cell += 1;
+
+        // Transform TMX coords into bevy coords.
+        let mapped_y = tiled_map.map.height - 1 - y;
+
+        let mapped_x = x as i32;
+        let mapped_y = mapped_y as i32;
+
+        let layer_tile = match layer_data.get_tile(mapped_x, mapped_y) {
+            Some(t) => t,
+            None => {
+                continue;
+            }
+        };
+        if pending.tileset_index != layer_tile.tileset_index() {
+            continue;
+        }
+        let layer_tile_data = match layer_data.get_tile_data(mapped_x, mapped_y) {
+            Some(d) => d,
+            None => {
+                continue;
+            }
+        };
+
+        let tile_pos = TilePos { x, y };
+        let tile_entity = spawn_tile(
+            commands,
+            tiled_map,
+            pending.layer_entity,
+            tile_pos,
+            TiledTile {
+                tileset_index: pending.tileset_index,
+                id: layer_tile.id(),
+            },
+            TileFlip {
+                x: layer_tile_data.flip_h,
+                y: layer_tile_data.flip_v,
+                d: layer_tile_data.flip_d,
+            },
+        )
+        .expect("The offset into to image vector should have been saved during the initial load.");
+        tile_storage.set(&tile_pos, tile_entity);
+
+        *budget -= 1;
+        progress.spawned += 1;
+    }
+
+    pending.next_cell >= width * height
+}
+
+/// Despawns the tilemap layers of a map, their tiles and the tiles' colliders.
+fn despawn_layers(
+    commands: &mut Commands,
+    map_entity: Entity,
+    layer_query: &Query<(Entity, &TiledLayer, &TileStorage)>,
+) {
+    for (layer_entity, layer, layer_tile_storage) in layer_query.iter() {
+        if layer.map != map_entity {
+            continue;
+        }
+        for tile in layer_tile_storage.iter().flatten() {
+            commands.entity(*tile).despawn();
+        }
+        commands.entity(layer_entity).despawn();
+    }
+}
+
+fn despawn_objects(
+    commands: &mut Commands,
+    map_entity: Entity,
+    object_query: &Query<(Entity, &TiledObject)>,
+) {
+    for (object_entity, object) in object_query.iter() {
+        if object.map == map_entity {
+            commands.entity(object_entity).despawn();
+        }
+    }
+}
+
+/// Layers, tiles and objects are not children of the map, so they have to be despawned along
+/// with it.
+fn despawn_removed_map(
+    remove: On<Remove, TiledMapHandle>,
+    mut commands: Commands,
+    layer_query: Query<(Entity, &TiledLayer, &TileStorage)>,
+    object_query: Query<(Entity, &TiledObject)>,
+) {
+    despawn_layers(&mut commands, remove.entity, &layer_query);
+    despawn_objects(&mut commands, remove.entity, &object_query);
+}
+
+/// Spawning maps in headless tests.
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+
+    /// A 3×2 map with a ground layer and a walls layer. Tile 1 of its tileset has a collider.
+    pub(crate) const SMALL_MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" tiledversion="1.11.2" orientation="orthogonal" renderorder="right-down" width="3" height="2" tilewidth="16" tileheight="16" infinite="0" nextlayerid="3" nextobjectid="1">
+ <tileset firstgid="1" name="tiles" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+  <image source="tiles.png" width="32" height="32"/>
+  <tile id="1">
+   <objectgroup draworder="index" id="2">
+    <object id="1" x="0" y="0" width="16" height="16"/>
+   </objectgroup>
+  </tile>
+ </tileset>
+ <layer id="1" name="Ground" width="3" height="2">
+  <data encoding="csv">
+1,1,1,
+1,1,1
+</data>
+ </layer>
+ <layer id="2" name="Walls" width="3" height="2">
+  <data encoding="csv">
+2,0,0,
+0,0,2
+</data>
+ </layer>
+</map>
+"#;
+
+    /// An app spawning maps the way [`plugin`] does, without the asset server.
+    pub(crate) fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Assets<TiledMap>>()
+            .add_message::<AssetEvent<TiledMap>>()
+            .init_resource::<MapSpawnBudget>()
+            .add_systems(Update, (process_loaded_maps, spawn_map_tiles).chain())
+            .add_observer(despawn_removed_map);
+        app
+    }
+
+    /// Loads a map the way [`TiledLoader`] does, with placeholder images.
+    pub(crate) fn load_map(bytes: &[u8], path: &str) -> TiledMap {
+        let map = tiled::Loader::with_cache_and_reader(
+            tiled::DefaultResourceCache::new(),
+            BytesResourceReader::new(bytes),
+        )
+        .load_tmx_map(Path::new(path))
+        .expect("map should load");
+        let tilemap_textures = (0..map.tilesets().len())
+            .map(|tileset_index| (tileset_index, TilemapTexture::Single(Handle::default())))
+            .collect();
+        TiledMap {
+            pre_colliders: pre_colliders(&map),
+            map,
+            tilemap_textures,
+            #[cfg(not(feature = "atlas"))]
+            tile_image_offsets: HashMap::default(),
+        }
+    }
+
+    /// Spawns a map and runs the app until all of its tiles are spawned.
+    pub(crate) fn spawn_map(app: &mut App, tiled_map: TiledMap) -> Entity {
+        let handle = app
+            .world_mut()
+            .resource_mut::<Assets<TiledMap>>()
+            .add(tiled_map);
+        let map = app
+            .world_mut()
+            .spawn(TiledMapBundle {
+                tiled_map: TiledMapHandle(handle),
+                ..Default::default()
+            })
+            .id();
+        for _ in 0..10 {
+            app.update();
+            if app
+                .world()
+                .get::<MapSpawnProgress>(map)
+                .is_some_and(MapSpawnProgress::is_built)
+            {
+                return map;
             }
         }
+        panic!("map should be spawned within 10 frames");
     }
 }
@@ -0,0 +1,302 @@
+//! Ask a spawned map what is under a world position, so that footsteps, slowdowns or hazards
+//! can be driven by custom properties set in Tiled.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_ecs_tilemap::prelude::*;
+use tiled::PropertyValue;
+
+use crate::utils::tiled::{TiledLayer, TiledMap, TiledMapHandle, TiledTile};
+
+/// Converts between world positions and [`TilePos`] for spawned maps, and looks up the Tiled
+/// data of the tiles found there.
+#[derive(SystemParam)]
+pub struct TiledMapQuery<'w, 's> {
+    maps: Res<'w, Assets<TiledMap>>,
+    map_query: Query<'w, 's, &'static TiledMapHandle>,
+    layer_query: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static TiledLayer,
+            &'static TileStorage,
+            &'static TilemapSize,
+            &'static TilemapGridSize,
+            &'static TilemapTileSize,
+            &'static TilemapType,
+            &'static TilemapAnchor,
+            &'static GlobalTransform,
+        ),
+    >,
+    tile_query: Query<'w, 's, &'static TiledTile>,
+}
+
+/// A tile found through [`TiledMapQuery`].
+pub struct TileInfo<'a> {
+    pub tile_entity: Entity,
+    pub tile_pos: TilePos,
+    /// Index of the layer in [`tiled::Map::layers`].
+    pub layer_index: u32,
+    pub layer: tiled::Layer<'a>,
+    pub tileset_index: usize,
+    pub tile_id: tiled::TileId,
+    /// `None` when the tileset has no custom data (properties, collision, ...) for this tile.
+    pub tile: Option<tiled::Tile<'a>>,
+}
+
+impl TileInfo<'_> {
+    /// The custom property `name` of the tile, falling back to the one of its layer.
+    pub fn property(&self, name: &str) -> Option<&PropertyValue> {
+        self.tile
+            .as_ref()
+            .and_then(|tile| tile.properties.get(name))
+            .or_else(|| self.layer.properties.get(name))
+    }
+}
+
+impl<'w, 's> TiledMapQuery<'w, 's> {
+    /// The loaded asset of a map entity.
+    pub fn map(&self, map: Entity) -> Option<&TiledMap> {
+        let handle = self.map_query.get(map).ok()?;
+        self.maps.get(&handle.0)
+    }
+
+    /// The tile position under `world_pos` in the given layer, if it is inside the map.
+    pub fn world_to_tile_pos(
+        &self,
+        map: Entity,
+        layer_index: u32,
+        world_pos: Vec2,
+    ) -> Option<TilePos> {
+        self.layers(map)
+            .find(|(_, layer, ..)| layer.layer_index == layer_index)
+            .and_then(
+                |(_, _, _, size, grid_size, tile_size, map_type, anchor, transform)| {
+                    let local_pos = transform
+                        .affine()
+                        .inverse()
+                        .transform_point3(world_pos.extend(0.0))
+                        .truncate();
+                    TilePos::from_world_pos(
+                        &local_pos, size, grid_size, tile_size, map_type, anchor,
+                    )
+                },
+            )
+    }
+
+    /// The world position of the center of a tile in the given layer.
+    pub fn tile_pos_to_world(
+        &self,
+        map: Entity,
+        layer_index: u32,
+        tile_pos: &TilePos,
+    ) -> Option<Vec2> {
+        self.layers(map)
+            .find(|(_, layer, ..)| layer.layer_index == layer_index)
+            .map(
+                |(_, _, _, size, grid_size, tile_size, map_type, anchor, transform)| {
+                    let local_pos =
+                        tile_pos.center_in_world(size, grid_size, tile_size, map_type, anchor);
+                    transform.transform_point(local_pos.extend(0.0)).truncate()
+                },
+            )
+    }
+
+    /// The tile under `world_pos` in the given layer.
+    pub fn tile_at(&self, map: Entity, layer_index: u32, world_pos: Vec2) -> Option<TileInfo<'_>> {
+        let tile_pos = self.world_to_tile_pos(map, layer_index, world_pos)?;
+        // A layer mixing tilesets is spawned as several tilemaps sharing the same grid.
+        self.layers(map)
+            .filter(|(_, layer, ..)| layer.layer_index == layer_index)
+            .find_map(|(_, layer, storage, ..)| self.tile_info(map, layer, storage, tile_pos))
+    }
+
+    /// Every tile under `world_pos`, from the topmost layer down.
+    pub fn tiles_at(&self, map: Entity, world_pos: Vec2) -> Vec<TileInfo<'_>> {
+        let mut tiles: Vec<_> = self
+            .layers(map)
+            .filter_map(|(_, layer, storage, ..)| {
+                let tile_pos = self.world_to_tile_pos(map, layer.layer_index, world_pos)?;
+                self.tile_info(map, layer, storage, tile_pos)
+            })
+            .collect();
+        tiles.sort_by(|a, b| b.layer_index.cmp(&a.layer_index));
+        tiles
+    }
+
+    /// The custom property `name` of the topmost tile under `world_pos` that has it, looking at
+    /// tile properties first and layer properties second.
+    pub fn property_at(&self, map: Entity, world_pos: Vec2, name: &str) -> Option<PropertyValue> {
+        self.tiles_at(map, world_pos)
+            .iter()
+            .find_map(|info| info.property(name).cloned())
+    }
+
+    /// The Tiled data of the tile at `tile_pos` in one spawned tilemap, if there is one.
+    fn tile_info(
+        &self,
+        map: Entity,
+        layer: &TiledLayer,
+        storage: &TileStorage,
+        tile_pos: TilePos,
+    ) -> Option<TileInfo<'_>> {
+        let tiled_map = self.map(map)?;
+        let tile_entity = storage.get(&tile_pos)?;
+        let tile = self.tile_query.get(tile_entity).ok()?;
+        let tiled_layer = tiled_map.map.get_layer(layer.layer_index as usize)?;
+        let tileset = &tiled_map.map.tilesets()[tile.tileset_index];
+
+        Some(TileInfo {
+            tile_entity,
+            tile_pos,
+            layer_index: layer.layer_index,
+            layer: tiled_layer,
+            tileset_index: tile.tileset_index,
+            tile_id: tile.id,
+            tile: tileset.get_tile(tile.id),
+        })
+    }
+
+    fn layers(
+        &self,
+        map: Entity,
+    ) -> impl Iterator<
+        Item = (
+            Entity,
+            &TiledLayer,
+            &TileStorage,
+            &TilemapSize,
+            &TilemapGridSize,
+            &TilemapTileSize,
+            &TilemapType,
+            &TilemapAnchor,
+            &GlobalTransform,
+        ),
+    > {
+        self.layer_query
+            .iter()
+            .filter(move |(_, layer, ..)| layer.map == map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    const MAP_SIZE: TilemapSize = TilemapSize { x: 4, y: 3 };
+    const GRID_SIZE: TilemapGridSize = TilemapGridSize { x: 16.0, y: 16.0 };
+
+    /// A map of two layers spawned the way [`process_loaded_maps`](super::super::process_loaded_maps)
+    /// does, the second one offset like a Tiled layer offset.
+    fn spawn_map(world: &mut World) -> Entity {
+        world.init_resource::<Assets<TiledMap>>();
+        let map = world.spawn(TiledMapHandle::default()).id();
+        for (layer_index, offset) in [(0, Vec2::ZERO), (1, Vec2::new(8.0, -4.0))] {
+            world.spawn((
+                TiledLayer {
+                    map,
+                    layer_index,
+                    tileset_index: 0,
+                },
+                TileStorage::empty(MAP_SIZE),
+                MAP_SIZE,
+                GRID_SIZE,
+                TilemapTileSize { x: 16.0, y: 16.0 },
+                TilemapType::Square,
+                TilemapAnchor::Center,
+                GlobalTransform::from_translation(offset.extend(layer_index as f32)),
+            ));
+        }
+        map
+    }
+
+    fn tile_positions() -> impl Iterator<Item = TilePos> {
+        (0..MAP_SIZE.x).flat_map(|x| (0..MAP_SIZE.y).map(move |y| TilePos { x, y }))
+    }
+
+    #[test]
+    fn tiles_go_to_their_center_and_back() {
+        let mut world = World::new();
+        let map = spawn_map(&mut world);
+        world
+            .run_system_once(move |query: TiledMapQuery| {
+                for layer_index in [0, 1] {
+                    for tile_pos in tile_positions() {
+                        let center = query
+                            .tile_pos_to_world(map, layer_index, &tile_pos)
+                            .expect("layer should exist");
+                        assert_eq!(
+                            query.world_to_tile_pos(map, layer_index, center),
+                            Some(tile_pos),
+                            "{tile_pos:?} of layer {layer_index} at {center}"
+                        );
+                    }
+                }
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn positions_go_to_the_center_of_their_tile() {
+        let mut world = World::new();
+        let map = spawn_map(&mut world);
+        world
+            .run_system_once(move |query: TiledMapQuery| {
+                // The map is centred: its bottom-left tile is at the bottom left of the origin.
+                assert_eq!(
+                    query.tile_pos_to_world(map, 0, &TilePos { x: 0, y: 0 }),
+                    Some(Vec2::new(-24.0, -16.0))
+                );
+                for layer_index in [0, 1] {
+                    for x in (-31..32).step_by(3) {
+                        for y in (-23..24).step_by(3) {
+                            let world_pos = Vec2::new(x as f32, y as f32)
+                                + if layer_index == 1 {
+                                    Vec2::new(8.0, -4.0)
+                                } else {
+                                    Vec2::ZERO
+                                };
+                            let tile_pos = query
+                                .world_to_tile_pos(map, layer_index, world_pos)
+                                .expect("position should be inside the map");
+                            let center = query
+                                .tile_pos_to_world(map, layer_index, &tile_pos)
+                                .unwrap();
+                            let offset = (world_pos - center).abs();
+                            assert!(
+                                offset.x <= GRID_SIZE.x / 2.0 && offset.y <= GRID_SIZE.y / 2.0,
+                                "{world_pos} of layer {layer_index} went to {center}"
+                            );
+                        }
+                    }
+                }
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn layers_keep_their_own_offset() {
+        let mut world = World::new();
+        let map = spawn_map(&mut world);
+        world
+            .run_system_once(move |query: TiledMapQuery| {
+                // Just inside the bottom-left corner of the first layer, which the second one
+                // no longer covers.
+                let world_pos = Vec2::new(-31.0, -20.0);
+                assert_eq!(
+                    query.world_to_tile_pos(map, 0, world_pos),
+                    Some(TilePos { x: 0, y: 0 })
+                );
+                assert_eq!(query.world_to_tile_pos(map, 1, world_pos), None);
+                assert_eq!(query.world_to_tile_pos(map, 2, world_pos), None);
+                assert_eq!(
+                    query.world_to_tile_pos(map, 0, Vec2::new(40.0, 0.0)),
+                    None,
+                    "outside the map"
+                );
+            })
+            .unwrap();
+    }
+}
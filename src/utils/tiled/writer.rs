@@ -0,0 +1,657 @@
+//! Write a spawned map back out as TMX, with its runtime edits applied, so it can be loaded again
+//! through [`TiledLoader`](super::TiledLoader).
+//!
+//! Tile layers are written from the spawned [`TileStorage`]s. Everything else (tilesets, object
+//! layers, properties) is written from the loaded [`TiledMap`]. External tilesets stay external
+//! and embedded tilesets stay embedded, see the note in the README on why maps carry both.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_ecs_tilemap::prelude::*;
+use thiserror::Error;
+use tiled::{
+    HorizontalAlignment, ObjectData, ObjectShape, PropertyValue, StaggerAxis, StaggerIndex,
+    Tileset, TilesetLocation, VerticalAlignment,
+};
+
+use crate::utils::tiled::{TiledLayer, TiledMap, TiledMapHandle, TiledTile};
+
+const FLIPPED_HORIZONTALLY_FLAG: u32 = 0x80000000;
+const FLIPPED_VERTICALLY_FLAG: u32 = 0x40000000;
+const FLIPPED_DIAGONALLY_FLAG: u32 = 0x20000000;
+
+#[derive(Debug, Error)]
+pub enum TmxWriteError {
+    #[error("Entity {0} is not a map")]
+    NotAMap(Entity),
+    #[error("The map of entity {0} is not loaded")]
+    NotLoaded(Entity),
+    #[error("Could not write TMX file: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Saves a spawned map, with its runtime edits, to a TMX file.
+#[derive(Debug, Clone)]
+pub struct SaveMap {
+    /// The entity holding the [`TiledMapHandle`].
+    pub map: Entity,
+    pub path: PathBuf,
+}
+
+impl Command for SaveMap {
+    fn apply(self, world: &mut World) {
+        match map_to_tmx(world, self.map)
+            .and_then(|tmx| std::fs::write(&self.path, tmx).map_err(TmxWriteError::from))
+        {
+            Ok(()) => info!("Saved map to {}", self.path.display()),
+            Err(e) => warn!("Could not save map to {}: {e}", self.path.display()),
+        }
+    }
+}
+
+/// The TMX document of a spawned map.
+pub fn map_to_tmx(world: &mut World, map: Entity) -> Result<String, TmxWriteError> {
+    let handle = world
+        .get::<TiledMapHandle>(map)
+        .ok_or(TmxWriteError::NotAMap(map))?
+        .0
+        .clone();
+
+    let mut layer_query = world.query::<(&TiledLayer, &TileStorage)>();
+    let mut tile_query = world.query::<(&TilePos, &TiledTile, &TileFlip)>();
+    let world = &*world;
+    let tiled_map = world
+        .resource::<Assets<TiledMap>>()
+        .get(&handle)
+        .ok_or(TmxWriteError::NotLoaded(map))?;
+    let first_gids = first_gids(&tiled_map.map);
+    let (width, height) = (tiled_map.map.width, tiled_map.map.height);
+
+    // Global tile ids of every tile layer, in TMX order (rows from the top).
+    let mut layer_gids = HashMap::<u32, Vec<u32>>::default();
+    for (layer, storage) in layer_query.iter(world) {
+        if layer.map != map {
+            continue;
+        }
+        let gids = layer_gids
+            .entry(layer.layer_index)
+            .or_insert_with(|| vec![0; (width * height) as usize]);
+        for tile_entity in storage.iter().flatten() {
+            let Ok((pos, tile, flip)) = tile_query.get(world, *tile_entity) else {
+                continue;
+            };
+            let mut gid = first_gids[tile.tileset_index] + tile.id;
+            if flip.x {
+                gid |= FLIPPED_HORIZONTALLY_FLAG;
+            }
+            if flip.y {
+                gid |= FLIPPED_VERTICALLY_FLAG;
+            }
+            if flip.d {
+                gid |= FLIPPED_DIAGONALLY_FLAG;
+            }
+            // Transform bevy coords into TMX coords.
+            gids[((height - 1 - pos.y) * width + pos.x) as usize] = gid;
+        }
+    }
+
+    Ok(write_map(&tiled_map.map, &layer_gids))
+}
+
+/// Tiled requires the global ids of the tilesets not to overlap, which assigning them in order
+/// guarantees.
+fn first_gids(map: &tiled::Map) -> Vec<u32> {
+    let mut first_gid = 1;
+    map.tilesets()
+        .iter()
+        .map(|tileset| {
+            let gid = first_gid;
+            let max_id = tileset.tiles().map(|(id, _)| id + 1).max().unwrap_or(0);
+            first_gid += tileset.tilecount.max(max_id);
+            gid
+        })
+        .collect()
+}
+
+fn write_map(map: &tiled::Map, layer_gids: &HashMap<u32, Vec<u32>>) -> String {
+    let orientation = match map.orientation {
+        tiled::Orientation::Orthogonal => "orthogonal",
+        tiled::Orientation::Isometric => "isometric",
+        tiled::Orientation::Staggered => "staggered",
+        tiled::Orientation::Hexagonal => "hexagonal",
+    };
+    let next_layer_id = map.layers().map(|layer| layer.id()).max().unwrap_or(0) + 1;
+    let next_object_id = map
+        .layers()
+        .filter_map(|layer| match layer.layer_type() {
+            tiled::LayerType::Objects(objects) => {
+                objects.object_data().iter().map(|o| o.id()).max()
+            }
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+        + 1;
+
+    // Only hexagonal and staggered maps have a stagger, and only hexagonal ones a side length.
+    let mut stagger = String::new();
+    if let tiled::Orientation::Hexagonal = map.orientation
+        && let Some(hex_side_length) = map.hex_side_length
+    {
+        let _ = write!(stagger, " hexsidelength=\"{hex_side_length}\"");
+    }
+    if let tiled::Orientation::Hexagonal | tiled::Orientation::Staggered = map.orientation {
+        let axis = match map.stagger_axis {
+            StaggerAxis::X => "x",
+            StaggerAxis::Y => "y",
+        };
+        let index = match map.stagger_index {
+            StaggerIndex::Even => "even",
+            StaggerIndex::Odd => "odd",
+        };
+        let _ = write!(stagger, " staggeraxis=\"{axis}\" staggerindex=\"{index}\"");
+    }
+
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<map version=\"1.10\" orientation=\"{orientation}\" renderorder=\"right-down\" width=\"{}\" height=\"{}\" tilewidth=\"{}\" tileheight=\"{}\"{stagger} infinite=\"0\" nextlayerid=\"{next_layer_id}\" nextobjectid=\"{next_object_id}\">",
+        map.width, map.height, map.tile_width, map.tile_height
+    );
+    write_properties(&mut out, 1, &map.properties);
+
+    let first_gids = first_gids(map);
+    for (tileset, first_gid) in map.tilesets().iter().zip(&first_gids) {
+        write_tileset(&mut out, map, tileset, *first_gid, &first_gids);
+    }
+
+    for (layer_index, layer) in map.layers().enumerate() {
+        let mut attributes = format!("id=\"{}\" name=\"{}\"", layer.id(), escape(&layer.name));
+        if !layer.visible {
+            attributes.push_str(" visible=\"0\"");
+        }
+        if layer.opacity != 1.0 {
+            let _ = write!(attributes, " opacity=\"{}\"", layer.opacity);
+        }
+        if layer.offset_x != 0.0 || layer.offset_y != 0.0 {
+            let _ = write!(
+                attributes,
+                " offsetx=\"{}\" offsety=\"{}\"",
+                layer.offset_x, layer.offset_y
+            );
+        }
+
+        match layer.layer_type() {
+            tiled::LayerType::Tiles(tiled::TileLayer::Finite(_)) => {
+                let _ = writeln!(
+                    out,
+                    " <layer {attributes} width=\"{}\" height=\"{}\">",
+                    map.width, map.height
+                );
+                write_properties(&mut out, 2, &layer.properties);
+                out.push_str("  <data encoding=\"csv\">\n");
+                let empty = vec![0; (map.width * map.height) as usize];
+                let gids = layer_gids.get(&(layer_index as u32)).unwrap_or(&empty);
+                let rows: Vec<String> = gids
+                    .chunks(map.width as usize)
+                    .map(|row| row.iter().map(u32::to_string).collect::<Vec<_>>().join(","))
+                    .collect();
+                out.push_str(&rows.join(",\n"));
+                out.push_str("\n</data>\n </layer>\n");
+            }
+            tiled::LayerType::Objects(objects) => {
+                let _ = writeln!(out, " <objectgroup {attributes}>");
+                write_properties(&mut out, 2, &layer.properties);
+                for object in objects.object_data() {
+                    write_object(&mut out, 2, object, &first_gids);
+                }
+                out.push_str(" </objectgroup>\n");
+            }
+            _ => warn!(
+                "Skipping layer {} because only finite tile layers and object layers are saved.",
+                layer.id()
+            ),
+        }
+    }
+
+    out.push_str("</map>\n");
+    out
+}
+
+fn write_tileset(
+    out: &mut String,
+    map: &tiled::Map,
+    tileset: &Tileset,
+    first_gid: u32,
+    first_gids: &[u32],
+) {
+    let is_external_tileset = tileset
+        .source
+        .extension()
+        .is_some_and(|ext| ext.to_ascii_lowercase() == "tsx");
+    if is_external_tileset {
+        let _ = writeln!(
+            out,
+            " <tileset firstgid=\"{first_gid}\" source=\"{}\"/>",
+            escape(&relative_to_map(map, &tileset.source))
+        );
+        return;
+    }
+
+    let _ = writeln!(
+        out,
+        " <tileset firstgid=\"{first_gid}\" name=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" spacing=\"{}\" margin=\"{}\" tilecount=\"{}\" columns=\"{}\">",
+        escape(&tileset.name),
+        tileset.tile_width,
+        tileset.tile_height,
+        tileset.spacing,
+        tileset.margin,
+        tileset.tilecount,
+        tileset.columns
+    );
+    write_properties(out, 2, &tileset.properties);
+    if let Some(image) = &tileset.image {
+        let _ = writeln!(
+            out,
+            "  <image source=\"{}\" width=\"{}\" height=\"{}\"/>",
+            escape(&relative_to_map(map, &image.source)),
+            image.width,
+            image.height
+        );
+    }
+
+    let mut tiles: Vec<_> = tileset.tiles().collect();
+    tiles.sort_by_key(|(id, _)| *id);
+    for (id, tile) in tiles {
+        let _ = writeln!(out, "  <tile id=\"{id}\">");
+        write_properties(out, 3, &tile.properties);
+        if let Some(image) = &tile.image {
+            let _ = writeln!(
+                out,
+                "   <image source=\"{}\" width=\"{}\" height=\"{}\"/>",
+                escape(&relative_to_map(map, &image.source)),
+                image.width,
+                image.height
+            );
+        }
+        if let Some(collision) = &tile.collision {
+            out.push_str("   <objectgroup draworder=\"index\">\n");
+            for object in collision.object_data() {
+                write_object(out, 4, object, first_gids);
+            }
+            out.push_str("   </objectgroup>\n");
+        }
+        out.push_str("  </tile>\n");
+    }
+    out.push_str(" </tileset>\n");
+}
+
+fn write_object(out: &mut String, depth: usize, object: &ObjectData, first_gids: &[u32]) {
+    let indent = " ".repeat(depth);
+    let mut attributes = format!("id=\"{}\"", object.id());
+    if !object.name.is_empty() {
+        let _ = write!(attributes, " name=\"{}\"", escape(&object.name));
+    }
+    if !object.user_type.is_empty() {
+        let _ = write!(attributes, " type=\"{}\"", escape(&object.user_type));
+    }
+    let _ = write!(attributes, " x=\"{}\" y=\"{}\"", object.x, object.y);
+    if object.rotation != 0.0 {
+        let _ = write!(attributes, " rotation=\"{}\"", object.rotation);
+    }
+    if !object.visible {
+        attributes.push_str(" visible=\"0\"");
+    }
+    if let Some(tile) = &object.tile {
+        let TilesetLocation::Map(tileset_index) = tile.tileset_location() else {
+            warn!(
+                "Skipping object {} because tile objects of templates are not saved.",
+                object.id()
+            );
+            return;
+        };
+        let mut gid = first_gids[*tileset_index] + tile.id();
+        if tile.flip_h {
+            gid |= FLIPPED_HORIZONTALLY_FLAG;
+        }
+        if tile.flip_v {
+            gid |= FLIPPED_VERTICALLY_FLAG;
+        }
+        if tile.flip_d {
+            gid |= FLIPPED_DIAGONALLY_FLAG;
+        }
+        let _ = write!(attributes, " gid=\"{gid}\"");
+    }
+
+    let shape = match &object.shape {
+        ObjectShape::Rect { width, height } => {
+            let _ = write!(attributes, " width=\"{width}\" height=\"{height}\"");
+            None
+        }
+        ObjectShape::Ellipse { width, height } => {
+            let _ = write!(attributes, " width=\"{width}\" height=\"{height}\"");
+            Some("<ellipse/>".to_string())
+        }
+        ObjectShape::Polyline { points } => Some(format!(
+            "<polyline points=\"{}\"/>",
+            points_to_string(points)
+        )),
+        ObjectShape::Polygon { points } => Some(format!(
+            "<polygon points=\"{}\"/>",
+            points_to_string(points)
+        )),
+        ObjectShape::Point(_, _) => Some("<point/>".to_string()),
+        ObjectShape::Text {
+            font_family,
+            pixel_size,
+            wrap,
+            color,
+            bold,
+            italic,
+            underline,
+            strikeout,
+            kerning,
+            halign,
+            valign,
+            text,
+            width,
+            height,
+        } => {
+            let _ = write!(attributes, " width=\"{width}\" height=\"{height}\"");
+            let halign = match halign {
+                HorizontalAlignment::Left => "left",
+                HorizontalAlignment::Center => "center",
+                HorizontalAlignment::Right => "right",
+                HorizontalAlignment::Justify => "justify",
+            };
+            let valign = match valign {
+                VerticalAlignment::Top => "top",
+                VerticalAlignment::Center => "center",
+                VerticalAlignment::Bottom => "bottom",
+            };
+            Some(format!(
+                "<text fontfamily=\"{}\" pixelsize=\"{pixel_size}\" wrap=\"{}\" color=\"#{:02x}{:02x}{:02x}{:02x}\" bold=\"{}\" italic=\"{}\" underline=\"{}\" strikeout=\"{}\" kerning=\"{}\" halign=\"{halign}\" valign=\"{valign}\">{}</text>",
+                escape(font_family),
+                u8::from(*wrap),
+                color.alpha,
+                color.red,
+                color.green,
+                color.blue,
+                u8::from(*bold),
+                u8::from(*italic),
+                u8::from(*underline),
+                u8::from(*strikeout),
+                u8::from(*kerning),
+                escape(text)
+            ))
+        }
+    };
+
+    if shape.is_none() && object.properties.is_empty() {
+        let _ = writeln!(out, "{indent}<object {attributes}/>");
+        return;
+    }
+    let _ = writeln!(out, "{indent}<object {attributes}>");
+    write_properties(out, depth + 1, &object.properties);
+    if let Some(shape) = shape {
+        let _ = writeln!(out, "{indent} {shape}");
+    }
+    let _ = writeln!(out, "{indent}</object>");
+}
+
+fn write_properties(out: &mut String, depth: usize, properties: &tiled::Properties) {
+    if properties.is_empty() {
+        return;
+    }
+    let indent = " ".repeat(depth);
+    let _ = writeln!(out, "{indent}<properties>");
+    let mut properties: Vec<_> = properties.iter().collect();
+    properties.sort_by_key(|(name, _)| *name);
+    for (name, value) in properties {
+        let name = escape(name);
+        let (kind, value) = match value {
+            PropertyValue::BoolValue(v) => ("bool", v.to_string()),
+            PropertyValue::FloatValue(v) => ("float", v.to_string()),
+            PropertyValue::IntValue(v) => ("int", v.to_string()),
+            PropertyValue::ColorValue(c) => (
+                "color",
+                format!("#{:02x}{:02x}{:02x}{:02x}", c.alpha, c.red, c.green, c.blue),
+            ),
+            PropertyValue::StringValue(v) => ("string", escape(v)),
+            PropertyValue::FileValue(v) => ("file", escape(v)),
+            PropertyValue::ObjectValue(v) => ("object", v.to_string()),
+            PropertyValue::ClassValue {
+                property_type,
+                properties,
+            } => {
+                let _ = writeln!(
+                    out,
+                    "{indent} <property name=\"{name}\" type=\"class\" propertytype=\"{}\">",
+                    escape(property_type)
+                );
+                write_properties(out, depth + 2, properties);
+                let _ = writeln!(out, "{indent} </property>");
+                continue;
+            }
+        };
+        let _ = writeln!(
+            out,
+            "{indent} <property name=\"{name}\" type=\"{kind}\" value=\"{value}\"/>"
+        );
+    }
+    let _ = writeln!(out, "{indent}</properties>");
+}
+
+fn points_to_string(points: &[(f32, f32)]) -> String {
+    points
+        .iter()
+        .map(|(x, y)| format!("{x},{y}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Paths in the loaded map are relative to the assets directory, the ones in TMX files are
+/// relative to the map.
+fn relative_to_map(map: &tiled::Map, path: &Path) -> String {
+    let relative = map
+        .source
+        .parent()
+        .and_then(|map_dir| path.strip_prefix(map_dir).ok())
+        .unwrap_or(path);
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tiled::{
+        TiledLayersStorage,
+        testing::{app, load_map, spawn_map},
+    };
+
+    const MAP_PATH: &str = "tiled/map1.tile-16x16.tmx";
+
+    /// A hexagonal map with a flipped tile object and a text object.
+    const HEX_MAP: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" tiledversion="1.11.2" orientation="hexagonal" renderorder="right-down" width="3" height="2" tilewidth="14" tileheight="12" infinite="0" hexsidelength="6" staggeraxis="y" staggerindex="odd" nextlayerid="3" nextobjectid="3">
+ <tileset firstgid="1" name="hexes" tilewidth="14" tileheight="12" tilecount="4" columns="2">
+  <image source="hexes.png" width="28" height="24"/>
+ </tileset>
+ <layer id="1" name="Ground" width="3" height="2">
+  <data encoding="csv">
+1,2,1,
+2,1,2
+</data>
+ </layer>
+ <objectgroup id="2" name="Labels">
+  <object id="1" name="Sign" type="Sign" gid="2147483652" x="14" y="24" width="14" height="12"/>
+  <object id="2" name="Caption" x="4" y="2" width="40" height="10">
+   <text fontfamily="monospace" pixelsize="8" wrap="1" color="#ff336699" bold="1" halign="center" valign="bottom">Beware &amp; behold</text>
+  </object>
+ </objectgroup>
+</map>
+"##;
+
+    /// Spawns a map, applies `edit` to the spawned world, saves it with [`map_to_tmx`] and loads
+    /// the result back. Returns the original map and the saved one.
+    fn round_trip(
+        bytes: &[u8],
+        path: &str,
+        edit: impl FnOnce(&mut World, Entity),
+    ) -> (tiled::Map, tiled::Map) {
+        let mut app = app();
+        let map = spawn_map(&mut app, load_map(bytes, path));
+        edit(app.world_mut(), map);
+        let tmx = map_to_tmx(app.world_mut(), map).expect("map should be saved");
+        (
+            load_map(bytes, path).map,
+            load_map(tmx.as_bytes(), path).map,
+        )
+    }
+
+    /// Every tile of each tile layer, with its flips, in TMX order (rows from the top).
+    fn tiles(map: &tiled::Map) -> Vec<Vec<Option<(usize, u32, bool, bool, bool)>>> {
+        map.layers()
+            .filter_map(|layer| layer.as_tile_layer())
+            .map(|tile_layer| {
+                let mut tiles = Vec::new();
+                for y in 0..map.height as i32 {
+                    for x in 0..map.width as i32 {
+                        tiles.push(tile_layer.get_tile(x, y).map(|tile| {
+                            (
+                                tile.tileset_index(),
+                                tile.id(),
+                                tile.flip_h,
+                                tile.flip_v,
+                                tile.flip_d,
+                            )
+                        }));
+                    }
+                }
+                tiles
+            })
+            .collect()
+    }
+
+    /// The tile of a tile object, with its flips.
+    fn object_tile(object: &ObjectData) -> Option<(usize, u32, bool, bool, bool)> {
+        object.tile.as_ref().map(|tile| {
+            let TilesetLocation::Map(tileset_index) = tile.tileset_location() else {
+                panic!("object {} comes from a template", object.id());
+            };
+            (
+                *tileset_index,
+                tile.id(),
+                tile.flip_h,
+                tile.flip_v,
+                tile.flip_d,
+            )
+        })
+    }
+
+    /// Compares everything but the tiles of tile layers.
+    fn assert_same_layers_and_objects(written: &tiled::Map, map: &tiled::Map) {
+        assert_eq!(written.orientation, map.orientation);
+        assert_eq!(written.properties, map.properties);
+        assert_eq!(written.tilesets().len(), map.tilesets().len());
+        let layers: Vec<_> = map.layers().collect();
+        let written_layers: Vec<_> = written.layers().collect();
+        assert_eq!(written_layers.len(), layers.len());
+        for (written_layer, layer) in written_layers.iter().zip(&layers) {
+            assert_eq!(written_layer.id(), layer.id());
+            assert_eq!(written_layer.name, layer.name);
+            assert_eq!(written_layer.properties, layer.properties);
+            assert_eq!(
+                written_layer.as_object_layer().is_some(),
+                layer.as_object_layer().is_some()
+            );
+            if let (Some(written_objects), Some(objects)) =
+                (written_layer.as_object_layer(), layer.as_object_layer())
+            {
+                let written_objects = written_objects.object_data();
+                let objects = objects.object_data();
+                assert_eq!(written_objects.len(), objects.len());
+                for (written_object, object) in written_objects.iter().zip(objects) {
+                    assert_eq!(written_object.id(), object.id());
+                    assert_eq!(written_object.name, object.name);
+                    assert_eq!(written_object.user_type, object.user_type);
+                    assert_eq!((written_object.x, written_object.y), (object.x, object.y));
+                    assert_eq!(written_object.shape, object.shape);
+                    assert_eq!(written_object.properties, object.properties);
+                    assert_eq!(object_tile(written_object), object_tile(object));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn saved_map_loads_back_with_its_edits() {
+        let bytes = std::fs::read(Path::new("assets").join(MAP_PATH)).unwrap();
+        let map = load_map(&bytes, MAP_PATH).map;
+        let first_layer = map
+            .layers()
+            .position(|layer| layer.as_tile_layer().is_some())
+            .unwrap() as u32;
+        let first_tile = tiles(&map)[0].iter().position(Option::is_some).unwrap() as u32;
+
+        // A runtime edit: flip the first tile of the first tile layer every way.
+        let (map, written) = round_trip(&bytes, MAP_PATH, |world, map_entity| {
+            let layer_entity =
+                world.get::<TiledLayersStorage>(map_entity).unwrap().storage[&first_layer];
+            // Transform TMX coords into bevy coords.
+            let tile_pos = TilePos {
+                x: first_tile % map.width,
+                y: map.height - 1 - first_tile / map.width,
+            };
+            let tile_entity = world
+                .get::<TileStorage>(layer_entity)
+                .unwrap()
+                .get(&tile_pos)
+                .unwrap();
+            *world.get_mut::<TileFlip>(tile_entity).unwrap() = TileFlip {
+                x: true,
+                y: true,
+                d: true,
+            };
+        });
+
+        assert_same_layers_and_objects(&written, &map);
+        let mut expected = tiles(&map);
+        let edited = expected[0][first_tile as usize].as_mut().unwrap();
+        (edited.2, edited.3, edited.4) = (true, true, true);
+        assert_eq!(tiles(&written), expected);
+    }
+
+    #[test]
+    fn hexagonal_maps_keep_their_stagger_and_objects() {
+        let (map, written) = round_trip(HEX_MAP.as_bytes(), "hex.tmx", |_, _| {});
+
+        assert_same_layers_and_objects(&written, &map);
+        assert_eq!(tiles(&written), tiles(&map));
+        assert_eq!(written.hex_side_length, map.hex_side_length);
+        assert_eq!(written.stagger_axis, map.stagger_axis);
+        assert_eq!(written.stagger_index, map.stagger_index);
+        let objects = map
+            .layers()
+            .find_map(|layer| layer.as_object_layer())
+            .unwrap();
+        assert!(
+            objects.object_data()[0].tile.is_some(),
+            "the fixture has a tile object"
+        );
+        assert!(
+            matches!(objects.object_data()[1].shape, ObjectShape::Text { .. }),
+            "the fixture has a text object"
+        );
+    }
+}